@@ -0,0 +1,537 @@
+//! A command line tool that generates identicon files in bulk.
+
+#![deny(missing_docs)]
+
+extern crate clap;
+extern crate csv;
+extern crate identicons;
+
+use clap::{App, Arg, ArgMatches};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Read};
+use std::path::PathBuf;
+use std::process;
+
+use identicons::{
+    EmojiStyle, GenerationOptions, Generator, Identicon, Registry, RenderOptions, SeedKey,
+};
+
+fn main() {
+    let matches = app().get_matches();
+    let result = Config::from_matches(&matches).and_then(|config| config.run());
+    if let Err(err) = result {
+        eprintln!("identicons: {}", err);
+        process::exit(1);
+    }
+}
+
+fn app() -> App<'static, 'static> {
+    App::new("identicons")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Generates identicon files from seeds")
+        .arg(
+            Arg::with_name("seeds")
+                .value_name("SEED")
+                .multiple(true)
+                .help("Seeds to generate icons for. Use - to read one seed per line from stdin."),
+        )
+        .arg(
+            Arg::with_name("csv")
+                .long("csv")
+                .value_name("FILE")
+                .conflicts_with("seeds")
+                .requires("column")
+                .help("Read seeds from a CSV file with a header row"),
+        )
+        .arg(
+            Arg::with_name("column")
+                .long("column")
+                .value_name("COLUMN")
+                .requires("csv")
+                .help("The CSV column to read seeds from, by name or by number counting from 1"),
+        )
+        .arg(
+            Arg::with_name("family")
+                .short("f")
+                .long("family")
+                .value_name("FAMILY")
+                .default_value("shield")
+                .help("The family of icons to generate"),
+        )
+        .arg(
+            Arg::with_name("icon-version")
+                .long("icon-version")
+                .value_name("VERSION")
+                .help("The version of the family to use [default: the latest]"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short("t")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(&["svg", "png", "webp", "json"])
+                .default_value("svg")
+                .help("The format of the files to write"),
+        )
+        .arg(
+            Arg::with_name("size")
+                .short("s")
+                .long("size")
+                .value_name("PIXELS")
+                .default_value("200")
+                .help("The width and height of each image"),
+        )
+        .arg(
+            Arg::with_name("vector-emoji")
+                .long("vector-emoji")
+                .help("Draw emoji in SVGs from vector artwork instead of an emoji font"),
+        )
+        .arg(
+            Arg::with_name("key-file")
+                .long("key-file")
+                .value_name("FILE")
+                .help(
+                    "Key seeds with the secret in this file, like the server's \
+                     IDENTICONS_SEED_KEY_FILE. Families that can't be keyed, such as blockies, \
+                     can't be used with it.",
+                ),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("DIR")
+                .default_value(".")
+                .help("The directory to write files to"),
+        )
+        .arg(
+            Arg::with_name("name")
+                .short("n")
+                .long("name")
+                .value_name("TEMPLATE")
+                .default_value("{seed}.{format}")
+                .help(
+                    "The name of each file. {seed}, {index}, {family}, {version} and {format} \
+                     are replaced by the icon's details. Characters in seeds that aren't \
+                     safe in file names are replaced with _.",
+                ),
+        )
+}
+
+/// The largest image size that can be requested, in pixels.
+const MAX_SIZE: u32 = 2048;
+
+/// Where to read seeds from.
+#[derive(Debug, PartialEq)]
+enum Source {
+    /// Seeds given as arguments.
+    Args(Vec<String>),
+    /// One seed per line of stdin.
+    Stdin,
+    /// A column of a CSV file.
+    Csv { path: PathBuf, column: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Svg,
+    Png,
+    WebP,
+    Json,
+}
+
+impl Format {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "svg" => Some(Format::Svg),
+            "png" => Some(Format::Png),
+            "webp" => Some(Format::WebP),
+            "json" => Some(Format::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Png => "png",
+            Format::WebP => "webp",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// Everything needed to generate a batch of icons.
+#[derive(Debug)]
+struct Config {
+    source: Source,
+    generator: Generator,
+    generation: GenerationOptions,
+    render: RenderOptions,
+    format: Format,
+    output: PathBuf,
+    name: String,
+}
+
+impl Config {
+    fn from_matches(matches: &ArgMatches) -> Result<Self, String> {
+        let source = match (matches.values_of("seeds"), matches.value_of("csv")) {
+            (_, Some(path)) => Source::Csv {
+                path: PathBuf::from(path),
+                column: matches.value_of("column").unwrap().to_string(),
+            },
+            (Some(ref seeds), None)
+                if seeds.len() == 1 && matches.value_of("seeds") == Some("-") =>
+            {
+                Source::Stdin
+            }
+            (Some(seeds), None) => Source::Args(seeds.map(|s| s.to_string()).collect()),
+            (None, None) => {
+                return Err("give some seeds, - to read them from stdin, or --csv".to_string())
+            }
+        };
+
+        let registry = Registry::default();
+        let family = matches.value_of("family").unwrap();
+        let generator = match matches.value_of("icon-version") {
+            Some(version) => {
+                let version = version
+                    .trim_start_matches('v')
+                    .parse()
+                    .map_err(|_| format!("{:?} isn't a version", version))?;
+                registry.get(family, version)
+            }
+            None => registry.latest(family),
+        };
+        let generator = match generator {
+            Some(generator) => *generator,
+            None => {
+                let available: Vec<String> = registry
+                    .generators()
+                    .iter()
+                    .map(|g| format!("{} v{}", g.family(), g.version()))
+                    .collect();
+                return Err(format!(
+                    "there is no such generator; choose from {}",
+                    available.join(", ")
+                ));
+            }
+        };
+
+        let mut generation = GenerationOptions::default();
+        if let Some(path) = matches.value_of("key-file") {
+            if !generator.accepts_key() {
+                return Err(format!(
+                    "{} icons can't be keyed, so --key-file can't be used with them",
+                    generator.family()
+                ));
+            }
+            generation.key = Some(SeedKey::from_file(path).map_err(|err| err.to_string())?);
+        }
+
+        let size = matches.value_of("size").unwrap();
+        let size = match size.parse() {
+            Ok(size) if size > 0 && size <= MAX_SIZE => size,
+            _ => {
+                return Err(format!(
+                    "size must be between 1 and {}, not {}",
+                    MAX_SIZE, size
+                ))
+            }
+        };
+        let render = RenderOptions {
+            size,
+            emoji: if matches.is_present("vector-emoji") {
+                EmojiStyle::Vector
+            } else {
+                EmojiStyle::Text
+            },
+            ..RenderOptions::default()
+        };
+
+        Ok(Config {
+            source,
+            generator,
+            generation,
+            render,
+            format: Format::from_extension(matches.value_of("format").unwrap()).unwrap(),
+            output: PathBuf::from(matches.value_of("output").unwrap()),
+            name: matches.value_of("name").unwrap().to_string(),
+        })
+    }
+
+    /// Generate an icon for every seed, and write them to files, printing
+    /// the path of each one.
+    fn run(&self) -> Result<(), String> {
+        let seeds = match self.source {
+            Source::Args(ref seeds) => seeds.clone(),
+            Source::Stdin => {
+                let stdin = io::stdin();
+                let lines: Result<Vec<String>, _> = stdin.lock().lines().collect();
+                lines.map_err(|err| format!("couldn't read stdin: {}", err))?
+            }
+            Source::Csv {
+                ref path,
+                ref column,
+            } => {
+                let file = fs::File::open(path)
+                    .map_err(|err| format!("couldn't read {}: {}", path.display(), err))?;
+                csv_seeds(file, column)?
+            }
+        };
+
+        let names = self.file_names(&seeds)?;
+        for (seed, name) in seeds.iter().zip(names) {
+            let path = self.output.join(name);
+            let icon = self
+                .generator
+                .from_seed_with_options(seed, &self.generation)
+                .map_err(|err| format!("couldn't generate an icon for {:?}: {}", seed, err))?;
+            let content = self
+                .render(&*icon)
+                .map_err(|err| format!("couldn't render an icon for {:?}: {}", seed, err))?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|err| format!("couldn't create {}: {}", parent.display(), err))?;
+            }
+            fs::write(&path, content)
+                .map_err(|err| format!("couldn't write {}: {}", path.display(), err))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    }
+
+    fn render(&self, icon: &dyn Identicon) -> Result<Vec<u8>, identicons::Error> {
+        Ok(match self.format {
+            Format::Svg => icon.to_svg(&self.render)?.into_bytes(),
+            Format::Png => icon.to_png(self.render.size)?,
+            Format::WebP => icon.to_webp(self.render.size)?,
+            Format::Json => icon.to_json()?.into_bytes(),
+        })
+    }
+
+    /// The name of the file for each seed.
+    ///
+    /// Returns an error, before anything is written, if two seeds would be
+    /// written to the same file, such as `a/b` and `a_b`, or a seed that is
+    /// repeated. Including `{index}` in the name template avoids this.
+    fn file_names(&self, seeds: &[String]) -> Result<Vec<String>, String> {
+        let mut seen: HashMap<String, &str> = HashMap::new();
+        let mut names = Vec::with_capacity(seeds.len());
+        for (index, seed) in seeds.iter().enumerate() {
+            let name = self.file_name(index, seed);
+            if let Some(other) = seen.insert(name.clone(), seed) {
+                return Err(format!(
+                    "the seeds {:?} and {:?} would both be written to {}; \
+                     add {{index}} to --name to tell them apart",
+                    other, seed, name
+                ));
+            }
+            names.push(name);
+        }
+        Ok(names)
+    }
+
+    /// The name of the file for the `index`th seed, from the name template.
+    fn file_name(&self, index: usize, seed: &str) -> String {
+        self.name
+            .replace("{seed}", &safe_file_name(seed))
+            .replace("{index}", &index.to_string())
+            .replace("{family}", self.generator.family())
+            .replace("{version}", &self.generator.version().to_string())
+            .replace("{format}", self.format.extension())
+    }
+}
+
+/// Read the seeds in a column of a CSV file, which is named by its header or
+/// by its number, counting from 1.
+fn csv_seeds<R: Read>(reader: R, column: &str) -> Result<Vec<String>, String> {
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader
+        .headers()
+        .map_err(|err| format!("couldn't read CSV headers: {}", err))?
+        .clone();
+    let index = match headers.iter().position(|h| h == column) {
+        Some(index) => index,
+        None => match column.parse::<usize>() {
+            Ok(number) if number >= 1 && number <= headers.len() => number - 1,
+            _ => return Err(format!("the CSV file has no column {:?}", column)),
+        },
+    };
+
+    let mut seeds = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|err| format!("couldn't read CSV: {}", err))?;
+        match record.get(index) {
+            Some(seed) => seeds.push(seed.to_string()),
+            None => {
+                return Err(format!(
+                    "CSV row {} has no column {:?}",
+                    seeds.len() + 2,
+                    column
+                ))
+            }
+        }
+    }
+    Ok(seeds)
+}
+
+/// `seed`, with anything that isn't safe in a file name replaced with `_`.
+fn safe_file_name(seed: &str) -> String {
+    let safe: String = seed
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.@+".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match safe.as_str() {
+        "" | "." | ".." => safe.replace('.', "_") + "_",
+        _ => safe,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        let args = ["identicons"].iter().chain(args);
+        Config::from_matches(&app().get_matches_from(args))
+    }
+
+    #[test]
+    fn test_config() {
+        let config = parse(&["one", "two"]).unwrap();
+        assert_eq!(
+            config.source,
+            Source::Args(vec!["one".to_string(), "two".to_string()])
+        );
+        let registry = Registry::default();
+        let latest = registry.latest("shield").unwrap();
+        assert_eq!(config.generator.version(), latest.version());
+        assert_eq!(config.format, Format::Svg);
+        assert_eq!(config.render.size, 200);
+
+        let config = parse(&[
+            "-f",
+            "shape",
+            "--icon-version",
+            "v0",
+            "-t",
+            "png",
+            "-s",
+            "64",
+            "-",
+        ])
+        .unwrap();
+        assert_eq!(config.source, Source::Stdin);
+        assert_eq!(
+            (config.generator.family(), config.generator.version()),
+            ("shape", 0)
+        );
+        assert_eq!(config.format, Format::Png);
+        assert_eq!(config.render.size, 64);
+
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["-f", "hexagon", "one"]).is_err());
+        assert!(parse(&["--icon-version", "99", "one"]).is_err());
+        assert!(parse(&["-s", "0", "one"]).is_err());
+    }
+
+    #[test]
+    fn test_file_name() {
+        let config = parse(&[
+            "-n",
+            "{family}/v{version}/{index}-{seed}.{format}",
+            "-t",
+            "json",
+            "x",
+        ])
+        .unwrap();
+        let version = config.generator.version();
+        assert_eq!(
+            config.file_name(3, "me@example.com"),
+            format!("shield/v{}/3-me@example.com.json", version)
+        );
+
+        assert_eq!(safe_file_name("a/b\\c d"), "a_b_c_d");
+        assert_eq!(safe_file_name(".."), "___");
+        assert_eq!(safe_file_name(""), "_");
+    }
+
+    #[test]
+    fn test_csv_seeds() {
+        let csv = "id,email\n1,a@example.com\n2,\"b,c@example.com\"\n";
+        let expected = vec!["a@example.com".to_string(), "b,c@example.com".to_string()];
+        assert_eq!(csv_seeds(csv.as_bytes(), "email").unwrap(), expected);
+        assert_eq!(csv_seeds(csv.as_bytes(), "2").unwrap(), expected);
+        assert!(csv_seeds(csv.as_bytes(), "name").is_err());
+        assert!(csv_seeds(csv.as_bytes(), "3").is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = env::temp_dir().join(format!("identicons-cli-{}", process::id()));
+        let output = dir.to_str().unwrap();
+        let config = parse(&[
+            "-f",
+            "shape",
+            "--icon-version",
+            "0",
+            "-o",
+            output,
+            "one",
+            "two",
+        ])
+        .unwrap();
+        config.run().unwrap();
+
+        let icon = identicons::ShapeIconData::from_input("one").unwrap();
+        let expected = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("one.svg")).unwrap(), expected);
+        assert!(dir.join("two.svg").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_key_file() {
+        let path = env::temp_dir().join(format!("identicons-cli-key-{}", process::id()));
+        fs::write(&path, "secret\n").unwrap();
+        let key_file = path.to_str().unwrap();
+
+        let config = parse(&["--key-file", key_file, "one"]).unwrap();
+        assert_eq!(config.generation.key, Some(SeedKey::new("secret").unwrap()));
+        // Blockies can't be keyed, so the combination is refused up front.
+        let err = parse(&["-f", "blockies", "--key-file", key_file, "0xab"]).unwrap_err();
+        assert!(err.contains("can't be keyed"), "{}", err);
+
+        fs::remove_file(&path).unwrap();
+        assert!(parse(&["--key-file", key_file, "one"]).is_err());
+    }
+
+    #[test]
+    fn test_colliding_names() {
+        let dir = env::temp_dir().join(format!("identicons-cli-collide-{}", process::id()));
+        let output = dir.to_str().unwrap();
+        for seeds in &[&["a/b", "a_b"], &["one", "one"]] {
+            let mut args = vec!["-o", output];
+            args.extend(seeds.iter());
+            let err = parse(&args).unwrap().run().unwrap_err();
+            assert!(err.contains("would both be written to"), "{}", err);
+            // Nothing is written when names collide.
+            assert!(!dir.exists());
+        }
+
+        let config = parse(&["-o", output, "-n", "{index}-{seed}.{format}", "a/b", "a_b"]).unwrap();
+        config.run().unwrap();
+        assert!(dir.join("0-a_b.svg").exists());
+        assert!(dir.join("1-a_b.svg").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
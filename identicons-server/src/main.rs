@@ -6,22 +6,84 @@ extern crate actix_web;
 extern crate ctrlc;
 extern crate identicons;
 extern crate identicons_server;
+#[macro_use]
+extern crate lazy_static;
 extern crate listenfd;
 extern crate rand;
 extern crate serde;
 extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
+extern crate sha2;
 extern crate tera;
 
-use actix_web::{App, HttpRequest, HttpResponse, Path};
+use actix_web::{
+    dev::HttpResponseBuilder,
+    http::{header, StatusCode},
+    App, HttpMessage, HttpRequest, HttpResponse, Path, Query,
+};
 use listenfd::ListenFd;
+use sha2::{Digest, Sha512};
+use std::ffi::OsString;
 use std::{env, process};
 use tera::Context;
 
-use identicons::{ShapeIconData, ShieldIconData};
+use identicons::{GenerationOptions, Generator, Identicon, Registry, RenderOptions, SeedKey};
 use identicons_server::templ;
 
+lazy_static! {
+    static ref REGISTRY: Registry = Registry::default();
+    static ref OPTIONS: GenerationOptions =
+        match generation_options(env::var_os(SEED_KEY_VAR), env::var_os(SEED_KEY_FILE_VAR)) {
+            Ok(options) => options,
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        };
+}
+
+/// The environment variable to read a key for seeding icons from. Set this to
+/// keep people from working out the icon for a seed they can guess, such as
+/// an email address.
+const SEED_KEY_VAR: &str = "IDENTICONS_SEED_KEY";
+
+/// The environment variable naming a file to read a key for seeding icons
+/// from, instead of `SEED_KEY_VAR`. Trailing whitespace in the file is
+/// ignored.
+const SEED_KEY_FILE_VAR: &str = "IDENTICONS_SEED_KEY_FILE";
+
+/// The options to generate icons with, given the values of `SEED_KEY_VAR` and
+/// `SEED_KEY_FILE_VAR`.
+fn generation_options(
+    key: Option<OsString>,
+    key_file: Option<OsString>,
+) -> Result<GenerationOptions, String> {
+    let key = match (key, key_file) {
+        (Some(_), Some(_)) => {
+            return Err(format!(
+                "only one of {} and {} can be set",
+                SEED_KEY_VAR, SEED_KEY_FILE_VAR
+            ))
+        }
+        (Some(key), None) => {
+            let key = key.into_string().map_err(|_| {
+                format!(
+                    "{} must be valid unicode, or use {}",
+                    SEED_KEY_VAR, SEED_KEY_FILE_VAR
+                )
+            })?;
+            Some(SeedKey::new(key).map_err(|err| err.to_string())?)
+        }
+        (None, Some(path)) => Some(SeedKey::from_file(path).map_err(|err| err.to_string())?),
+        (None, None) => None,
+    };
+    Ok(GenerationOptions {
+        key,
+        ..GenerationOptions::default()
+    })
+}
+
 fn main() {
     // Rust doesn't have a ctrl-c handler itself, so when running as
     // PID 1 in Docker it doesn't respond to SIGINT. This prevents
@@ -31,6 +93,9 @@ fn main() {
         process::exit(0);
     }).expect("error setting ctrl-c handler");
 
+    // Check the configuration before accepting any requests.
+    lazy_static::initialize(&OPTIONS);
+
     let server = actix_web::server::new(|| make_app());
 
     // Re-use a passed file descriptor, or create a new one to listen on.
@@ -52,110 +117,602 @@ fn main() {
 fn make_app() -> App {
     App::new()
         .resource("/", |r| r.get().f(index))
-        .resource("/i/shield/v1/{seed}.{format}", |r| {
-            r.get().with(shield_generator)
-        })
-        .resource("/i/shape/v0/{seed}.{format}", |r| {
-            r.get().with(shape_generator)
+        .resource("/i/", |r| r.get().f(generator_list))
+        .resource("/i/{family}/{version}/{seed}.{format}", |r| {
+            r.get().with(generator)
         })
+        .resource("/i/{family}/{version}/{seed}", |r| r.get().with(generator))
+        .resource("/avatar/{hash}", |r| r.get().with(avatar))
+        .default_resource(|r| r.f(not_found))
+}
+
+fn not_found(req: HttpRequest) -> HttpResponse {
+    ApiError::not_found(format!("there is nothing at {}", req.path())).response(&req)
 }
 
 fn index(_: HttpRequest) -> impl actix_web::Responder {
-    let context = Context::new();
+    let mut context = Context::new();
+    context.add("generators", &available_generators());
     let content = templ::render("index.html.tera", &context).unwrap();
 
     HttpResponse::Ok().content_type("text/html").body(content)
 }
 
+/// A generator that can be requested, as described by `/i/`.
+#[derive(Debug, Serialize)]
+struct GeneratorListing {
+    family: &'static str,
+    version: u32,
+    latest: bool,
+    url: String,
+}
+
+fn available_generators() -> Vec<GeneratorListing> {
+    REGISTRY
+        .generators()
+        .into_iter()
+        .map(|g| GeneratorListing {
+            family: g.family(),
+            version: g.version(),
+            latest: REGISTRY.latest(g.family()).map(|l| l.version()) == Some(g.version()),
+            url: format!("/i/{}/v{}/{{seed}}.{{format}}", g.family(), g.version()),
+        })
+        .collect()
+}
+
+fn generator_list(_: HttpRequest) -> impl actix_web::Responder {
+    HttpResponse::Ok()
+        .content_type("application/json")
+        .body(serde_json::to_string(&available_generators()).unwrap())
+}
+
+/// The parts of an icon's URL. When there is no format, it is negotiated
+/// from the request's `Accept` header. Since the router splits the seed at
+/// its last `.`, `format` may really be the end of the seed.
 #[derive(Debug, Deserialize)]
 struct GeneratorInfo {
+    family: String,
+    version: String,
     seed: String,
-    format: GeneratorFormat,
+    format: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum GeneratorFormat {
-    #[serde(rename = "svg")]
     Svg,
-    #[serde(rename = "json")]
+    Png,
+    WebP,
     Json,
 }
 
-fn shield_generator(info: Path<GeneratorInfo>) -> Result<HttpResponse, GeneratorError> {
-    let icon_data = ShieldIconData::from_input(&info.seed[..])?;
+impl GeneratorFormat {
+    /// Every format, in the order they are preferred when a client accepts
+    /// more than one equally.
+    const ALL: [GeneratorFormat; 4] = [
+        GeneratorFormat::Svg,
+        GeneratorFormat::Png,
+        GeneratorFormat::WebP,
+        GeneratorFormat::Json,
+    ];
 
-    Ok(match info.format {
-        GeneratorFormat::Svg => {
-            let content = icon_data.to_svg()?;
-            HttpResponse::Ok()
-                .content_type("image/svg+xml")
-                .body(content)
+    /// The format with the file extension `extension`, if there is one.
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "svg" => Some(GeneratorFormat::Svg),
+            "png" => Some(GeneratorFormat::Png),
+            "webp" => Some(GeneratorFormat::WebP),
+            "json" => Some(GeneratorFormat::Json),
+            _ => None,
         }
-        GeneratorFormat::Json => {
-            let json = serde_json::to_string(&icon_data)?;
-            HttpResponse::Ok()
-                .content_type("application/json")
-                .body(json)
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            GeneratorFormat::Svg => "image/svg+xml",
+            GeneratorFormat::Png => "image/png",
+            GeneratorFormat::WebP => "image/webp",
+            GeneratorFormat::Json => "application/json",
         }
-    })
+    }
+
+    /// The format that an `Accept` header most prefers, or `None` if it
+    /// doesn't accept any of them.
+    ///
+    /// Each format gets the quality of the most specific media range that
+    /// matches it, so `image/*, image/png;q=0` accepts every image but PNG.
+    fn negotiate(accept: &str) -> Option<Self> {
+        let ranges = media_ranges(accept);
+        let mut best = None;
+        for &format in &GeneratorFormat::ALL {
+            let quality = accepted_quality(&ranges, format.content_type()).map(|(_, q)| q);
+            match (quality, best) {
+                (Some(q), Some((_, best_q))) if q > best_q => best = Some((format, q)),
+                (Some(q), None) if q > 0.0 => best = Some((format, q)),
+                _ => (),
+            }
+        }
+        best.map(|(format, _)| format)
+    }
+}
+
+/// The media ranges in an `Accept` header, with their qualities.
+fn media_ranges(accept: &str) -> Vec<(&str, f32)> {
+    accept
+        .split(',')
+        .map(|range| {
+            let mut parts = range.split(';').map(|part| part.trim());
+            let media_type = parts.next().unwrap_or("");
+            let quality = parts
+                .filter_map(|param| {
+                    let (name, value) = param.split_once('=')?;
+                    if name.trim().eq_ignore_ascii_case("q") {
+                        value.trim().parse().ok()
+                    } else {
+                        None
+                    }
+                })
+                .next()
+                .unwrap_or(1.0);
+            (media_type, quality)
+        })
+        .collect()
+}
+
+/// The quality `ranges` give `content_type`, from the most specific range
+/// that matches it, along with that range's specificity: 2 for the type
+/// itself, 1 for `type/*` and 0 for `*/*`. Media types are compared without
+/// regard to case.
+fn accepted_quality(ranges: &[(&str, f32)], content_type: &str) -> Option<(u8, f32)> {
+    let top_level = &content_type[..content_type.find('/').unwrap() + 1];
+    ranges
+        .iter()
+        .filter_map(|&(range, quality)| {
+            if range.eq_ignore_ascii_case(content_type) {
+                Some((2, quality))
+            } else if range.len() == top_level.len() + 1
+                && range.ends_with('*')
+                && range[..top_level.len()].eq_ignore_ascii_case(top_level)
+            {
+                Some((1, quality))
+            } else if range == "*/*" {
+                Some((0, quality))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|&(specificity, _)| specificity)
+}
+
+/// Generate the icon for `seed` with `generator`, customized by `options`.
+/// Families that can't be keyed, since they follow a reference
+/// implementation, are generated without the seed key.
+///
+/// An error from the generator means it can't use the seed, which is the
+/// client's fault.
+fn generate_icon(
+    generator: &Generator,
+    seed: &str,
+    options: &GenerationOptions,
+) -> Result<Box<dyn Identicon>, ApiError> {
+    let unkeyed;
+    let options = if options.key.is_some() && !generator.accepts_key() {
+        unkeyed = GenerationOptions {
+            key: None,
+            ..options.clone()
+        };
+        &unkeyed
+    } else {
+        options
+    };
+    generator
+        .from_seed_with_options(seed, options)
+        .map_err(|err| match err {
+            identicons::Error::InvalidData(message) => ApiError::bad_request(format!(
+                "there is no {} icon for that seed: {}",
+                generator.family(),
+                message
+            )),
+            err => ApiError::from(err),
+        })
 }
 
-fn shape_generator(info: Path<GeneratorInfo>) -> Result<impl actix_web::Responder, GeneratorError> {
-    let icon_data = ShapeIconData::from_input(&info.seed[..])?;
+/// Extensions of image formats that icons aren't available in. A URL that
+/// ends in one, in any case, gets an error, rather than an icon for a seed
+/// that ends in it.
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "avif", "bmp", "gif", "heic", "ico", "jpeg", "jpg", "tif", "tiff",
+];
 
-    Ok(match info.format {
-        GeneratorFormat::Svg => {
-            let content = icon_data.to_svg().unwrap();
-            HttpResponse::Ok()
-                .content_type("image/svg+xml")
-                .body(content)
+/// The size is parsed by `generator`, so that a bad one gets the same kind
+/// of error as any other bad request.
+#[derive(Debug, Deserialize)]
+struct SizeQuery {
+    size: Option<String>,
+}
+
+/// The largest image size that can be requested, in pixels.
+const MAX_SIZE: u32 = 2048;
+
+fn generator(
+    (req, info, query): (HttpRequest, Path<GeneratorInfo>, Query<SizeQuery>),
+) -> HttpResponse {
+    match generate(&req, &info, &query) {
+        Ok(response) => response,
+        Err(err) => err.response(&req),
+    }
+}
+
+fn generate(
+    req: &HttpRequest,
+    info: &GeneratorInfo,
+    query: &SizeQuery,
+) -> Result<HttpResponse, ApiError> {
+    let generator = match parse_version(&info.version).and_then(|v| REGISTRY.get(&info.family, v)) {
+        Some(generator) => generator,
+        None => {
+            return Err(ApiError::not_found(format!(
+                "there is no generator {}/{}",
+                info.family, info.version
+            )))
         }
-        GeneratorFormat::Json => {
-            let json = serde_json::to_string(&icon_data).unwrap(); // TODO better error handling
-            HttpResponse::Ok()
-                .content_type("application/json")
-                .body(json)
+    };
+
+    // A suffix that isn't a format, such as the `com` of an email address,
+    // is part of the seed, unless it asks for an image format that isn't
+    // available.
+    let (seed, extension) = match info.format {
+        Some(ref extension)
+            if GeneratorFormat::from_extension(extension).is_none()
+                && !IMAGE_EXTENSIONS
+                    .iter()
+                    .any(|image| extension.eq_ignore_ascii_case(image)) =>
+        {
+            (format!("{}.{}", info.seed, extension), None)
         }
-    })
+        Some(ref extension) => (info.seed.clone(), Some(extension)),
+        None => (info.seed.clone(), None),
+    };
+    let format = match extension {
+        Some(extension) => match GeneratorFormat::from_extension(extension) {
+            Some(format) => format,
+            None => {
+                return Err(ApiError::bad_request(format!(
+                    "{:?} isn't a format; use svg, png, webp or json",
+                    extension
+                )))
+            }
+        },
+        None => {
+            let accept = match req.headers().get(header::ACCEPT) {
+                Some(accept) => accept.to_str().unwrap_or(""),
+                None => "*/*",
+            };
+            match GeneratorFormat::negotiate(accept) {
+                Some(format) => format,
+                None => {
+                    return Err(ApiError::not_acceptable(
+                        "icons are available as image/svg+xml, image/png, image/webp or \
+                         application/json",
+                    ))
+                }
+            }
+        }
+    };
+
+    let mut options = RenderOptions::default();
+    if let Some(ref size) = query.size {
+        match size.parse() {
+            Ok(size) if size > 0 && size <= MAX_SIZE => options.size = size,
+            _ => {
+                return Err(ApiError::bad_request(format!(
+                    "size must be between 1 and {}",
+                    MAX_SIZE
+                )))
+            }
+        }
+    }
+
+    let icon_data = generate_icon(generator, &seed, &OPTIONS)?;
+    let etag = etag(&*icon_data, &format, options.size)?;
+    let respond = |builder| {
+        let mut builder = cached(builder, &etag, CACHE_CONTROL);
+        if extension.is_none() {
+            builder.header(header::VARY, "Accept");
+        }
+        builder
+    };
+    if matches_etag(req, &etag) {
+        return Ok(respond(HttpResponse::NotModified()).finish());
+    }
+
+    let body = match format {
+        GeneratorFormat::Svg => icon_data.to_svg(&options)?.into_bytes(),
+        GeneratorFormat::Png => icon_data.to_png(options.size)?,
+        GeneratorFormat::WebP => icon_data.to_webp(options.size)?,
+        GeneratorFormat::Json => icon_data.to_json()?.into_bytes(),
+    };
+    Ok(respond(HttpResponse::Ok())
+        .content_type(format.content_type())
+        .body(body))
 }
 
-#[derive(Debug)]
-struct GeneratorError;
+/// How long responses for versioned icons can be cached, since they never
+/// change.
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// How long avatar responses can be cached. `/avatar/` isn't versioned, and
+/// the generators it picks from may be changed, so they aren't immutable.
+const AVATAR_CACHE_CONTROL: &str = "public, max-age=86400";
+
+/// A strong ETag for an icon rendered in `format` at `size`.
+fn etag(icon: &dyn Identicon, format: &GeneratorFormat, size: u32) -> Result<String, ApiError> {
+    let renderer = format!(
+        "identicons {}, server {}",
+        identicons::VERSION,
+        env!("CARGO_PKG_VERSION")
+    );
+    etag_with_renderer(&renderer, icon, format, size)
+}
+
+/// A strong ETag for an icon rendered in `format` at `size` by `renderer`,
+/// which names the versions of the code that draws icons.
+///
+/// The icon's JSON stands in for its family, version, seed and generation
+/// options, since it is determined by them, and unlike the options it
+/// doesn't contain the seed key. The renderer is included so that a release
+/// that draws the same icon differently doesn't serve the new bytes under
+/// an old tag.
+fn etag_with_renderer(
+    renderer: &str,
+    icon: &dyn Identicon,
+    format: &GeneratorFormat,
+    size: u32,
+) -> Result<String, ApiError> {
+    let mut hasher = Sha512::default();
+    hasher.input(
+        format!(
+            "{}\n{}\n{}\n{:?}\n{}\n{}",
+            renderer,
+            icon.family(),
+            icon.version(),
+            format,
+            size,
+            icon.to_json()?
+        )
+        .as_bytes(),
+    );
+    let hex: String = hasher.result()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(format!("\"{}\"", hex))
+}
 
-impl actix_web::error::ResponseError for GeneratorError {
-    fn error_response(&self) -> HttpResponse {
-        HttpResponse::InternalServerError()
-            .content_type("text/plain")
-            .body(format!("{}", self))
+/// Does the request's `If-None-Match` header match `etag`?
+fn matches_etag(req: &HttpRequest, etag: &str) -> bool {
+    let header = match req.headers().get(header::IF_NONE_MATCH) {
+        Some(header) => header,
+        None => return false,
+    };
+    match header.to_str() {
+        // If-None-Match uses weak comparison, so a weak tag matches too.
+        Ok(tags) => tags
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag),
+        Err(_) => false,
     }
 }
 
-impl std::error::Error for GeneratorError {
-    fn description(&self) -> &str {
-        "There was an unknown error generating the image"
+/// Add caching headers for a response tagged with `etag`, that can be cached
+/// as described by `cache_control`.
+fn cached(
+    mut builder: HttpResponseBuilder,
+    etag: &str,
+    cache_control: &str,
+) -> HttpResponseBuilder {
+    builder
+        .header(header::ETAG, etag)
+        .header(header::CACHE_CONTROL, cache_control);
+    builder
+}
+
+/// The generators that `/avatar/` can serve, chosen with its `d` parameter.
+/// The first is used when `d` isn't one of them.
+///
+/// These are pinned, rather than the latest versions, so that avatars don't
+/// change when a new version is added.
+const AVATAR_GENERATORS: &[(&str, u32)] = &[("shield", 1), ("shape", 0), ("grid", 1)];
+
+/// The size of avatars when none, or an invalid one, is requested.
+const DEFAULT_AVATAR_SIZE: u32 = 80;
+
+/// The query parameters Gravatar accepts, in their short and long forms.
+/// Ratings are accepted but ignored, since every icon is suitable for all
+/// audiences.
+#[derive(Debug, Deserialize)]
+struct AvatarQuery {
+    s: Option<String>,
+    size: Option<String>,
+    d: Option<String>,
+    default: Option<String>,
+}
+
+/// Serve an avatar for a Gravatar style hash, so that clients that use
+/// Gravatar can switch to this server by changing their base URL.
+fn avatar((req, hash, query): (HttpRequest, Path<String>, Query<AvatarQuery>)) -> HttpResponse {
+    match render_avatar(&req, &hash, &query) {
+        Ok(response) => response,
+        Err(err) => err.response(&req),
     }
 }
 
-impl std::fmt::Display for GeneratorError {
-    fn fmt(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(fmt, "{}", (self as &std::error::Error).description())?;
-        Ok(())
+fn render_avatar(
+    req: &HttpRequest,
+    hash: &str,
+    query: &AvatarQuery,
+) -> Result<HttpResponse, ApiError> {
+    let bytes = match parse_avatar_hash(hash) {
+        Some(bytes) => bytes,
+        None => {
+            return Err(ApiError::not_found(
+                "avatars must be requested by an MD5 or SHA-256 hash",
+            ))
+        }
+    };
+
+    // Like Gravatar, fall back to the default size instead of failing.
+    let size = match query.s.as_ref().or(query.size.as_ref()).map(|s| s.parse()) {
+        Some(Ok(size)) if size > 0 && size <= MAX_SIZE => size,
+        _ => DEFAULT_AVATAR_SIZE,
+    };
+
+    // Gravatar's own defaults, such as `identicon` or a URL, get the first
+    // generator.
+    let requested = query.d.as_ref().or(query.default.as_ref());
+    let &(family, version) = AVATAR_GENERATORS
+        .iter()
+        .find(|&&(family, _)| requested.map(|d| d.as_str()) == Some(family))
+        .unwrap_or(&AVATAR_GENERATORS[0]);
+    let generator = REGISTRY
+        .get(family, version)
+        .expect("avatar generators are registered");
+
+    let icon_data = generator.from_bytes_with_options(&bytes, &OPTIONS)?;
+    let etag = etag(&*icon_data, &GeneratorFormat::Png, size)?;
+    if matches_etag(req, &etag) {
+        return Ok(cached(HttpResponse::NotModified(), &etag, AVATAR_CACHE_CONTROL).finish());
     }
+    let content = icon_data.to_png(size)?;
+    Ok(cached(HttpResponse::Ok(), &etag, AVATAR_CACHE_CONTROL)
+        .content_type("image/png")
+        .body(content))
 }
 
-macro_rules! from_for_generator_error {
-    ($t:ty) => {
-        impl From<$t> for GeneratorError {
-            fn from(_: $t) -> Self {
-                GeneratorError
-            }
+/// Decode the hash from an avatar URL, which is the hex of an MD5 or SHA-256
+/// hash, optionally followed by an image extension.
+fn parse_avatar_hash(hash: &str) -> Option<Vec<u8>> {
+    let hash = match hash.rfind('.') {
+        Some(dot)
+            if ["jpg", "jpeg", "png"]
+                .iter()
+                .any(|ext| hash[dot + 1..].eq_ignore_ascii_case(ext)) =>
+        {
+            &hash[..dot]
         }
+        _ => hash,
     };
+    let valid_length = hash.len() == 32 || hash.len() == 64;
+    if !valid_length || !hash.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hash.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hash[i..i + 2], 16).ok())
+        .collect()
 }
 
-from_for_generator_error!(());
-from_for_generator_error!(serde_json::Error);
-from_for_generator_error!(tera::Error);
+/// Parse a version from a URL, like `v1`.
+fn parse_version(version: &str) -> Option<u32> {
+    match version.strip_prefix('v') {
+        Some(number) => number.parse().ok(),
+        None => None,
+    }
+}
+
+/// An error to report to the client, with the status code to report it with.
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+/// The body of an error response, when the client accepts JSON.
+#[derive(Debug, Serialize)]
+struct ErrorBody<'a> {
+    status: u16,
+    message: &'a str,
+}
+
+impl ApiError {
+    /// The request can't be served as it was made, such as when it asks for
+    /// an unknown format.
+    fn bad_request<T: Into<String>>(message: T) -> Self {
+        ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message: message.into(),
+        }
+    }
+
+    /// There is nothing to serve for the request, such as when it asks for
+    /// an unknown family or version.
+    fn not_found<T: Into<String>>(message: T) -> Self {
+        ApiError {
+            status: StatusCode::NOT_FOUND,
+            message: message.into(),
+        }
+    }
+
+    /// None of the formats the request accepts are available.
+    fn not_acceptable<T: Into<String>>(message: T) -> Self {
+        ApiError {
+            status: StatusCode::NOT_ACCEPTABLE,
+            message: message.into(),
+        }
+    }
+
+    /// Respond to `req` with this error, as JSON if it asks for JSON at
+    /// least as much as plain text, and as plain text otherwise.
+    ///
+    /// Errors are never cached, and vary by `Accept` since their format
+    /// does.
+    fn response(&self, req: &HttpRequest) -> HttpResponse {
+        let accepts_json = match req.headers().get(header::ACCEPT) {
+            Some(accept) => accept
+                .to_str()
+                .map(|accept| {
+                    let ranges = media_ranges(accept);
+                    let text = accepted_quality(&ranges, "text/plain").map_or(0.0, |(_, q)| q);
+                    // Only a range that names JSON, or `application/*`,
+                    // counts, so that `*/*` still gets plain text.
+                    match accepted_quality(&ranges, "application/json") {
+                        Some((specificity, json)) => specificity > 0 && json > 0.0 && json >= text,
+                        None => false,
+                    }
+                })
+                .unwrap_or(false),
+            None => false,
+        };
+        let mut response = HttpResponse::build(self.status);
+        response
+            .header(header::VARY, "Accept")
+            .header(header::CACHE_CONTROL, "no-store");
+        if accepts_json {
+            let body = ErrorBody {
+                status: self.status.as_u16(),
+                message: &self.message,
+            };
+            response
+                .content_type("application/json")
+                .body(serde_json::to_string(&body).unwrap())
+        } else {
+            response
+                .content_type("text/plain")
+                .body(self.message.clone())
+        }
+    }
+}
+
+/// Errors from the library mean that an icon couldn't be generated or
+/// rendered, which is the server's fault.
+impl From<identicons::Error> for ApiError {
+    fn from(err: identicons::Error) -> Self {
+        ApiError {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("There was an error generating the image: {}", err),
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -163,7 +720,9 @@ mod tests {
     use actix_web::{
         http::{Method, StatusCode}, test, HttpMessage,
     };
+    use identicons::ShieldIconData;
     use std::default::Default;
+    use std::fs;
 
     #[test]
     fn test_index() {
@@ -172,6 +731,82 @@ mod tests {
         assert_eq!(res.status(), StatusCode::OK);
     }
 
+    #[test]
+    fn test_error_message() {
+        let err = ApiError::from(identicons::Error::InvalidData("oops".to_string()));
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            err.message,
+            "There was an error generating the image: invalid icon data: oops"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut srv = test::TestServer::with_factory(make_app);
+        let mut get = |path: &str, accept: &str| {
+            let req = srv
+                .client(Method::GET, path)
+                .header(header::ACCEPT, accept)
+                .finish()
+                .unwrap();
+            let res = srv.execute(req.send()).unwrap();
+            let status = res.status();
+            let content_type = res.headers().get(header::CONTENT_TYPE).cloned().unwrap();
+            let body = srv.execute(res.body()).unwrap();
+            (
+                status,
+                content_type,
+                String::from_utf8(body.to_vec()).unwrap(),
+            )
+        };
+
+        for &(path, status) in &[
+            ("/i/shield/v1/test.gif", StatusCode::BAD_REQUEST),
+            ("/i/shield/v1/test.GIF", StatusCode::BAD_REQUEST),
+            ("/i/shield/v1/test.png?size=big", StatusCode::BAD_REQUEST),
+            ("/i/shield/v1/test.png?size=4096", StatusCode::BAD_REQUEST),
+            ("/i/hexagon/v1/test.svg", StatusCode::NOT_FOUND),
+            ("/avatar/nothex", StatusCode::NOT_FOUND),
+            ("/nowhere", StatusCode::NOT_FOUND),
+        ] {
+            let (actual, content_type, text) = get(path, "image/*");
+            assert_eq!(actual, status, "{}", path);
+            assert_eq!(content_type, "text/plain", "{}", path);
+            assert!(!text.is_empty());
+
+            let (actual, content_type, json) = get(path, "application/json, */*");
+            assert_eq!(actual, status, "{}", path);
+            assert_eq!(content_type, "application/json", "{}", path);
+            let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+            assert_eq!(json["status"], status.as_u16());
+            assert_eq!(json["message"], text);
+        }
+
+        // JSON has to be wanted at least as much as plain text.
+        for accept in &[
+            "application/json;q=0, */*",
+            "*/*",
+            "application/json;q=0.5, text/plain",
+        ] {
+            let (_, content_type, _) = get("/i/hexagon/v1/test.svg", accept);
+            assert_eq!(content_type, "text/plain", "{}", accept);
+        }
+        let (_, content_type, _) = get("/i/hexagon/v1/test.svg", "application/*");
+        assert_eq!(content_type, "application/json");
+
+        let req = srv
+            .client(Method::GET, "/i/hexagon/v1/test.svg")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "Accept");
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-store"
+        );
+    }
+
     #[test]
     fn test_routing() {
         let mut srv = test::TestServer::with_factory(make_app);
@@ -194,5 +829,373 @@ mod tests {
             res.headers().get("content-type").unwrap(),
             "application/json"
         );
+
+        let req = srv
+            .client(Method::GET, "/i/shape/v0/test.png")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "image/png");
+
+        let req = srv
+            .client(Method::GET, "/i/grid/v1/test.svg")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("content-type").unwrap(), "image/svg+xml");
+        let body = srv.execute(res.body()).unwrap();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("h 1 v 1 h -1 z"));
+
+        let req = srv
+            .client(
+                Method::GET,
+                "/i/blockies/v1/0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359.svg",
+            )
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = srv.execute(res.body()).unwrap();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("hsl(62,84.17102983221412%,56.51313749840483%)"));
+    }
+
+    #[test]
+    fn test_caching() {
+        let mut srv = test::TestServer::with_factory(make_app);
+        let mut get = |path: &str, if_none_match: Option<&str>| {
+            let mut req = srv.client(Method::GET, path);
+            if let Some(tag) = if_none_match {
+                req.header(header::IF_NONE_MATCH, tag);
+            }
+            let res = srv.execute(req.finish().unwrap().send()).unwrap();
+            let tag = res
+                .headers()
+                .get(header::ETAG)
+                .map(|t| t.to_str().unwrap().to_string());
+            let cache_control = res.headers().get(header::CACHE_CONTROL).cloned();
+            (res.status(), tag, cache_control)
+        };
+
+        let (status, tag, cache_control) = get("/i/shield/v1/test.svg", None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cache_control.unwrap(), CACHE_CONTROL);
+        let tag = tag.unwrap();
+        assert!(tag.starts_with('"') && tag.ends_with('"'));
+
+        // The same request gets the same tag, and anything that changes the
+        // body changes it.
+        assert_eq!(get("/i/shield/v1/test.svg", None).1.unwrap(), tag);
+        for other in &[
+            "/i/shield/v1/test2.svg",
+            "/i/shield/v1/test.json",
+            "/i/shield/v1/test.svg?size=64",
+            "/i/shape/v0/test.svg",
+        ] {
+            assert_ne!(get(other, None).1.unwrap(), tag, "{}", other);
+        }
+
+        for matching in &[
+            tag.clone(),
+            format!("W/{}", tag),
+            format!("\"x\", {}", tag),
+            "*".to_string(),
+        ] {
+            let (status, etag, cache_control) = get("/i/shield/v1/test.svg", Some(matching));
+            assert_eq!(status, StatusCode::NOT_MODIFIED, "{}", matching);
+            assert_eq!(etag.unwrap(), tag);
+            assert_eq!(cache_control.unwrap(), CACHE_CONTROL);
+        }
+        assert_eq!(
+            get("/i/shield/v1/test.svg", Some("\"x\"")).0,
+            StatusCode::OK
+        );
+
+        // Avatars aren't versioned, so they are only cached for a while.
+        let (status, avatar_tag, cache_control) =
+            get("/avatar/0bc83cb571cd1c50ba6f3e8a78ef1346", None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(cache_control.unwrap(), AVATAR_CACHE_CONTROL);
+        let avatar_tag = avatar_tag.unwrap();
+        let (status, etag, cache_control) = get(
+            "/avatar/0bc83cb571cd1c50ba6f3e8a78ef1346",
+            Some(&avatar_tag),
+        );
+        assert_eq!(status, StatusCode::NOT_MODIFIED);
+        assert_eq!(etag.unwrap(), avatar_tag);
+        assert_eq!(cache_control.unwrap(), AVATAR_CACHE_CONTROL);
+
+        // Errors aren't cached.
+        let (status, tag, cache_control) = get("/i/hexagon/v1/test.svg", None);
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(tag.is_none());
+        assert_eq!(cache_control.unwrap(), "no-store");
+    }
+
+    #[test]
+    fn test_etag_renderer() {
+        // The same icon drawn by another release gets another tag.
+        let icon = ShieldIconData::from_seed("test").unwrap();
+        let format = GeneratorFormat::Svg;
+        let tag = |renderer| etag_with_renderer(renderer, &icon, &format, 64).unwrap();
+        assert_eq!(tag("identicons 0.2.0"), tag("identicons 0.2.0"));
+        assert_ne!(tag("identicons 0.2.0"), tag("identicons 0.2.1"));
+        assert_eq!(
+            etag(&icon, &format, 64).unwrap(),
+            tag(&format!(
+                "identicons {}, server {}",
+                identicons::VERSION,
+                env!("CARGO_PKG_VERSION")
+            ))
+        );
+    }
+
+    #[test]
+    fn test_negotiate() {
+        use GeneratorFormat::*;
+        for &(accept, expected) in &[
+            ("*/*", Some(Svg)),
+            ("image/png", Some(Png)),
+            ("image/webp,image/*;q=0.8", Some(WebP)),
+            ("application/json, text/plain;q=0.5", Some(Json)),
+            ("image/*, image/svg+xml;q=0", Some(Png)),
+            ("image/png;q=0.5, application/json;q=0.9", Some(Json)),
+            ("image/png;q=0.5, */*;q=0.1", Some(Png)),
+            ("text/html", None),
+            ("image/*;q=0", None),
+            ("IMAGE/PNG", Some(Png)),
+            ("Image/*, image/svg+xml;Q=0", Some(Png)),
+        ] {
+            assert_eq!(GeneratorFormat::negotiate(accept), expected, "{}", accept);
+        }
+    }
+
+    #[test]
+    fn test_content_negotiation() {
+        let mut srv = test::TestServer::with_factory(make_app);
+        let mut get = |path: &str, accept: Option<&str>| {
+            let mut req = srv.client(Method::GET, path);
+            if let Some(accept) = accept {
+                req.header(header::ACCEPT, accept);
+            }
+            let res = srv.execute(req.finish().unwrap().send()).unwrap();
+            let header = |name| {
+                res.headers()
+                    .get(name)
+                    .map(|value: &header::HeaderValue| value.to_str().unwrap().to_string())
+            };
+            (
+                res.status(),
+                header(header::CONTENT_TYPE),
+                header(header::VARY),
+            )
+        };
+
+        for &(accept, content_type) in &[
+            (None, "image/svg+xml"),
+            (Some("image/png"), "image/png"),
+            (Some("image/webp"), "image/webp"),
+            (Some("application/json"), "application/json"),
+        ] {
+            let (status, actual, vary) = get("/i/shield/v1/test", accept);
+            assert_eq!(status, StatusCode::OK, "{:?}", accept);
+            assert_eq!(actual.unwrap(), content_type);
+            assert_eq!(vary.unwrap(), "Accept");
+        }
+
+        let (status, _, _) = get("/i/shield/v1/test", Some("text/html"));
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+
+        // An explicit format wins, and doesn't vary.
+        let (status, content_type, vary) = get("/i/shield/v1/test.png", Some("image/webp"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.unwrap(), "image/png");
+        assert!(vary.is_none());
+        let (_, content_type, _) = get("/i/shape/v0/test.webp", None);
+        assert_eq!(content_type.unwrap(), "image/webp");
+        let (status, content_type, vary) = get("/i/shield/v1/test.SVG", None);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.unwrap(), "image/svg+xml");
+        assert!(vary.is_none());
+        let (_, content_type, _) = get("/i/shield/v1/test", Some("IMAGE/PNG"));
+        assert_eq!(content_type.unwrap(), "image/png");
+
+        // A suffix that isn't a format is part of the seed.
+        let (status, content_type, vary) = get("/i/shield/v1/me@example.com", Some("image/png"));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(content_type.unwrap(), "image/png");
+        assert_eq!(vary.unwrap(), "Accept");
+        let req = srv
+            .client(Method::GET, "/i/shield/v1/me@example.com")
+            .header(header::ACCEPT, "application/json")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        let body = srv.execute(res.body()).unwrap();
+        assert_eq!(
+            String::from_utf8(body.to_vec()).unwrap(),
+            ShieldIconData::from_seed("me@example.com")
+                .unwrap()
+                .to_json()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_unknown_generators() {
+        let mut srv = test::TestServer::with_factory(make_app);
+
+        for path in &[
+            "/i/hexagon/v1/test.svg",
+            "/i/shield/v0/test.svg",
+            "/i/shield/1/test.svg",
+            "/i/shield/vx/test.svg",
+        ] {
+            let req = srv.client(Method::GET, path).finish().unwrap();
+            let res = srv.execute(req.send()).unwrap();
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", path);
+        }
+    }
+
+    #[test]
+    fn test_generator_list() {
+        let mut srv = test::TestServer::with_factory(make_app);
+
+        let req = srv.client(Method::GET, "/i/").finish().unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = srv.execute(res.body()).unwrap();
+        let list: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let list = list.as_array().unwrap();
+        assert_eq!(list.len(), REGISTRY.generators().len());
+        let shields: Vec<_> = list.iter().filter(|g| g["family"] == "shield").collect();
+        assert_eq!(shields[0]["version"], 1);
+        assert_eq!(shields[0]["latest"], false);
+        assert_eq!(shields[0]["url"], "/i/shield/v1/{seed}.{format}");
+        let newest = shields[shields.len() - 1];
+        assert_eq!(newest["version"], ShieldIconData::LATEST_VERSION);
+        assert_eq!(newest["latest"], true);
+    }
+
+    #[test]
+    fn test_seed_key() {
+        let unkeyed = generation_options(None, None).unwrap();
+        assert_eq!(unkeyed, GenerationOptions::default());
+
+        let keyed = generation_options(Some("secret".into()), None).unwrap();
+        assert_eq!(keyed.key, Some(SeedKey::new("secret").unwrap()));
+
+        let path = env::temp_dir().join(format!("identicons-seed-key-{}", process::id()));
+        fs::write(&path, "secret\n").unwrap();
+        let from_file = generation_options(None, Some(path.clone().into())).unwrap();
+        assert_eq!(from_file, keyed);
+        assert!(generation_options(Some("secret".into()), Some(path.clone().into())).is_err());
+        fs::remove_file(&path).unwrap();
+        assert!(generation_options(None, Some(path.into())).is_err());
+
+        assert!(generation_options(Some("".into()), None).is_err());
+    }
+
+    #[test]
+    fn test_keyed_server() {
+        let keyed = generation_options(Some("secret".into()), None).unwrap();
+        let unkeyed = GenerationOptions::default();
+        let json = |family, version, seed, options| {
+            let generator = REGISTRY.get(family, version).unwrap();
+            generate_icon(generator, seed, options)
+                .unwrap()
+                .to_json()
+                .unwrap()
+        };
+
+        // Blockies are the same as anywhere else, even with a key.
+        let address = "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359";
+        assert_eq!(
+            json("blockies", 1, address, &keyed),
+            json("blockies", 1, address, &unkeyed)
+        );
+        // Every other family uses the key.
+        for generator in REGISTRY.generators() {
+            if generator.accepts_key() {
+                assert_ne!(
+                    json(generator.family(), generator.version(), "test", &keyed),
+                    json(generator.family(), generator.version(), "test", &unkeyed),
+                    "{:?}",
+                    generator
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_avatar() {
+        let mut srv = test::TestServer::with_factory(make_app);
+        let mut get = |path: &str| {
+            let req = srv.client(Method::GET, path).finish().unwrap();
+            let res = srv.execute(req.send()).unwrap();
+            let status = res.status();
+            (status, srv.execute(res.body()).unwrap())
+        };
+        // The width of a PNG is the first field of its header.
+        let width = |png: &[u8]| u32::from_be_bytes([png[16], png[17], png[18], png[19]]);
+
+        let md5 = "0bc83cb571cd1c50ba6f3e8a78ef1346";
+        let (status, plain) = get(&format!("/avatar/{}", md5));
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(width(&plain), DEFAULT_AVATAR_SIZE);
+        assert_eq!(get(&format!("/avatar/{}.jpg", md5.to_uppercase())).1, plain);
+        assert_eq!(get(&format!("/avatar/{}.JPG", md5)).1, plain);
+        assert_eq!(get(&format!("/avatar/{}.Png", md5)).1, plain);
+        assert_eq!(get(&format!("/avatar/{}?d=identicon&r=pg", md5)).1, plain);
+        assert_eq!(get(&format!("/avatar/{}?s=0", md5)).1, plain);
+        assert_ne!(get(&format!("/avatar/{}?d=shape", md5)).1, plain);
+        let (status, grid) = get(&format!("/avatar/{}?d=grid", md5));
+        assert_eq!(status, StatusCode::OK);
+        assert_ne!(grid, plain);
+        assert_eq!(width(&get(&format!("/avatar/{}?s=120", md5)).1), 120);
+        assert_eq!(width(&get(&format!("/avatar/{}?size=32", md5)).1), 32);
+
+        let sha256 = "8b1a9953c4611296a827abf8c47804d7e6c49c6b7d4a1c2e2a4f2e4e5b2b1e3f";
+        assert_eq!(get(&format!("/avatar/{}", sha256)).0, StatusCode::OK);
+
+        for hash in &[
+            "abc",
+            "+bc83cb571cd1c50ba6f3e8a78ef1346",
+            "zzc83cb571cd1c50ba6f3e8a78ef1346",
+            "0bc83cb571cd1c50ba6f3e8a78ef1346.gif",
+        ] {
+            assert_eq!(
+                get(&format!("/avatar/{}", hash)).0,
+                StatusCode::NOT_FOUND,
+                "{}",
+                hash
+            );
+        }
+    }
+
+    #[test]
+    fn test_size() {
+        let mut srv = test::TestServer::with_factory(make_app);
+
+        let req = srv
+            .client(Method::GET, "/i/shield/v1/test.svg?size=64")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let body = srv.execute(res.body()).unwrap();
+        assert!(String::from_utf8_lossy(&body).contains(r#"width="64" height="64""#));
+
+        let req = srv
+            .client(Method::GET, "/i/shape/v0/test.png?size=0")
+            .finish()
+            .unwrap();
+        let res = srv.execute(req.send()).unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
     }
 }
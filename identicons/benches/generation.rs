@@ -0,0 +1,40 @@
+//! Benchmarks for generating icons from seeds, as a bulk backfill would.
+//!
+//! Run them with `cargo bench -p identicons`.
+
+#[macro_use]
+extern crate criterion;
+extern crate identicons;
+
+use criterion::{black_box, Criterion};
+use identicons::{ShapeIconData, ShieldIconData};
+
+/// Seeds like the ones a bulk backfill would use.
+fn seeds() -> Vec<String> {
+    (0..100).map(|i| format!("user{}@example.com", i)).collect()
+}
+
+fn bench_shield(c: &mut Criterion) {
+    let seeds = seeds();
+    c.bench_function("shield from_input x100", move |b| {
+        b.iter(|| {
+            for seed in &seeds {
+                black_box(ShieldIconData::from_input(black_box(seed.as_str())).unwrap());
+            }
+        })
+    });
+}
+
+fn bench_shape(c: &mut Criterion) {
+    let seeds = seeds();
+    c.bench_function("shape from_input x100", move |b| {
+        b.iter(|| {
+            for seed in &seeds {
+                black_box(ShapeIconData::from_input(black_box(seed.as_str())).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, bench_shield, bench_shape);
+criterion_main!(benches);
@@ -0,0 +1,548 @@
+//! Icons that match the blockies algorithm, which many Ethereum wallets and
+//! block explorers use to draw an icon for each address.
+//!
+//! Blockies don't use `Genome`. They are drawn from the seed with the same
+//! random number generator as the reference implementation, so that the
+//! icon for an address looks the same here as it does in other apps.
+
+extern crate tera;
+
+use super::raster;
+use super::render::{RenderOptions, ViewBox};
+use super::{templ, Color, Error, GenerationOptions};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::str;
+use tiny_skia::{PathBuilder, Pixmap, Rect};
+
+/// The number of cells across a blockie, and down it, in the reference
+/// implementation's default icons.
+const SIZE: u8 = 8;
+
+/// A color, as the reference implementation chooses it, in CSS's HSL model.
+///
+/// In JSON, it is the same `hsl()` string as `css_color`, since JSON numbers
+/// don't always parse back to exactly the same `f64`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockiesColor {
+    /// The hue, in degrees.
+    pub hue: u16,
+    /// The saturation, as a percentage.
+    pub saturation: f64,
+    /// The lightness, as a percentage.
+    pub lightness: f64,
+}
+
+impl BlockiesColor {
+    /// The color as a CSS `hsl()` color, written the same way as the
+    /// reference implementation writes it.
+    pub fn css_color(&self) -> String {
+        format!("hsl({},{}%,{}%)", self.hue, self.saturation, self.lightness)
+    }
+
+    /// Parse a color written by `css_color`.
+    fn from_css_color(css: &str) -> Option<Self> {
+        let inner = css.trim_start_matches("hsl(").trim_end_matches(')');
+        let mut parts = inner.split(',');
+        let hue = parts.next()?.parse().ok()?;
+        let saturation = parts.next()?.trim_end_matches('%').parse().ok()?;
+        let lightness = parts.next()?.trim_end_matches('%').parse().ok()?;
+        if parts.next().is_some() || inner.len() + 5 != css.len() {
+            return None;
+        }
+        Some(BlockiesColor {
+            hue,
+            saturation,
+            lightness,
+        })
+    }
+
+    /// The color in RGB, converted the way CSS converts `hsl()` colors.
+    pub fn to_rgb(&self) -> Color {
+        let h = (self.hue % 360) as f64 / 360.0;
+        let s = self.saturation.clamp(0.0, 100.0) / 100.0;
+        let l = self.lightness.clamp(0.0, 100.0) / 100.0;
+        let q = if l < 0.5 {
+            l * (1.0 + s)
+        } else {
+            l + s - l * s
+        };
+        let p = 2.0 * l - q;
+        let channel = |t: f64| {
+            let t = if t < 0.0 {
+                t + 1.0
+            } else if t > 1.0 {
+                t - 1.0
+            } else {
+                t
+            };
+            let v = if t < 1.0 / 6.0 {
+                p + (q - p) * 6.0 * t
+            } else if t < 1.0 / 2.0 {
+                q
+            } else if t < 2.0 / 3.0 {
+                p + (q - p) * (2.0 / 3.0 - t) * 6.0
+            } else {
+                p
+            };
+            (v * 255.0).round() as u8
+        };
+        Color {
+            r: channel(h + 1.0 / 3.0),
+            g: channel(h),
+            b: channel(h - 1.0 / 3.0),
+        }
+    }
+}
+
+impl Serialize for BlockiesColor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.css_color())
+    }
+}
+
+impl<'de> Deserialize<'de> for BlockiesColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = String::deserialize(deserializer)?;
+        BlockiesColor::from_css_color(&css)
+            .ok_or_else(|| de::Error::custom(format!("{} is not an hsl() color", css)))
+    }
+}
+
+/// A description of a blockies icon.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockiesIconData {
+    /// The number of cells across the icon, and down it.
+    pub size: u8,
+    /// The color of cells that are `1`.
+    pub color: BlockiesColor,
+    /// The color behind the cells, used for cells that are `0`.
+    pub background: BlockiesColor,
+    /// The color of cells that are `2`.
+    pub spot_color: BlockiesColor,
+    /// Each cell, row by row from the top. A cell is `0` for the background,
+    /// `1` for the color or `2` for the spot color.
+    pub cells: Vec<Vec<u8>>,
+}
+
+impl BlockiesIconData {
+    /// Generate a BlockiesIconData from a seed, such as an Ethereum address.
+    ///
+    /// The seed is used exactly as it is given. Most apps lowercase
+    /// addresses before drawing them, so that an address has the same icon
+    /// with or without its checksum capitalization.
+    pub fn from_input<T: Into<String>>(input: T) -> Result<Self, Error> {
+        Self::from_bytes_with_options(input.into().as_bytes(), &GenerationOptions::default())
+    }
+
+    /// Generate a BlockiesIconData from the UTF-8 bytes of a seed.
+    ///
+    /// Blockies always follow the reference implementation, so this returns
+    /// an error if `options` has a key, or if `bytes` isn't valid UTF-8.
+    /// The other options are ignored.
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        if options.key.is_some() {
+            return Err(Error::SeedKey(
+                "blockies follow the reference implementation, so they can't be keyed".to_string(),
+            ));
+        }
+        match str::from_utf8(bytes) {
+            Ok(seed) => Ok(Self::generate(seed)),
+            Err(_) => Err(Error::InvalidData(
+                "blockies seeds must be valid UTF-8".to_string(),
+            )),
+        }
+    }
+
+    /// Always returns an error, since blockies are drawn from their seed
+    /// rather than from its hash.
+    pub fn from_digest_with_options(
+        _digest: &[u8; 64],
+        _options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        Err(Error::InvalidData(
+            "blockies can't be generated from a digest".to_string(),
+        ))
+    }
+
+    /// Render as an SVG.
+    pub fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        let (color_cells, spot_cells) = self.filled_cells()?;
+        let mut context = tera::Context::new();
+        context.add("icon", &self);
+        context.add("svg", &options.svg_attributes(self, self.view_box())?);
+        context.add("color", &self.color.css_color());
+        context.add("background", &self.background.css_color());
+        context.add("spot_color", &self.spot_color.css_color());
+        context.add("color_cells", &color_cells);
+        context.add("spot_cells", &spot_cells);
+        Ok(templ::render("blockies.svg.tera", &context)?)
+    }
+
+    /// Render as a `size`x`size` PNG, with the same geometry as the SVG.
+    pub fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_png(&self.to_pixmap(size)?)
+    }
+
+    /// Render as a `size`x`size` lossless WebP, drawn the same way as
+    /// `to_png`.
+    pub fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_webp(&self.to_pixmap(size)?)
+    }
+
+    fn to_pixmap(&self, size: u32) -> Result<Pixmap, Error> {
+        let view_box = self.view_box();
+        let (color_cells, spot_cells) = self.filled_cells()?;
+        raster::render(size, view_box, None, |pixmap, transform| {
+            let background = (0.0, 0.0, view_box.width, view_box.height);
+            raster::fill_rect(
+                pixmap,
+                background,
+                self.background.to_rgb(),
+                transform,
+                None,
+            );
+            for &(ref cells, color) in &[(color_cells, self.color), (spot_cells, self.spot_color)] {
+                let mut pb = PathBuilder::new();
+                for &(x, y) in cells {
+                    if let Some(rect) = Rect::from_xywh(x, y, 1.0, 1.0) {
+                        pb.push_rect(rect);
+                    }
+                }
+                if let Some(path) = pb.finish() {
+                    raster::fill_path(pixmap, &path, color.to_rgb(), transform, None);
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// The area the icon is drawn in. Each cell is one unit square, and
+    /// there is no margin, like the reference implementation's canvas.
+    fn view_box(&self) -> ViewBox {
+        ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: self.size as f32,
+            height: self.size as f32,
+        }
+    }
+
+    /// The top left corner of each cell in the color, and of each cell in the
+    /// spot color.
+    ///
+    /// Returns an error if `cells` isn't `size` rows of `size` cells, or has
+    /// a cell that isn't 0, 1 or 2.
+    #[allow(clippy::type_complexity)]
+    fn filled_cells(&self) -> Result<(Vec<(f32, f32)>, Vec<(f32, f32)>), Error> {
+        let size = self.size as usize;
+        if size == 0 || self.cells.len() != size || self.cells.iter().any(|r| r.len() != size) {
+            return Err(Error::InvalidData(format!(
+                "blockies of size {} need {} rows of {} cells",
+                size, size, size
+            )));
+        }
+        let mut color_cells = vec![];
+        let mut spot_cells = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                let corner = (x as f32, y as f32);
+                match cell {
+                    0 => (),
+                    1 => color_cells.push(corner),
+                    2 => spot_cells.push(corner),
+                    _ => {
+                        return Err(Error::InvalidData(format!(
+                            "{} is not a blockies cell",
+                            cell
+                        )))
+                    }
+                }
+            }
+        }
+        Ok((color_cells, spot_cells))
+    }
+
+    /// Draw the icon for `seed`, making the same choices in the same order
+    /// as the reference implementation.
+    fn generate(seed: &str) -> Self {
+        let mut random = Xorshift::new(seed);
+        let color = random.color();
+        let background = random.color();
+        let spot_color = random.color();
+
+        // Like `GridIconData`, only the left half is chosen, and the right
+        // half mirrors it.
+        let width = SIZE as usize;
+        let mut cells = vec![];
+        for _ in 0..width {
+            let mut row = vec![0; width];
+            for x in 0..width.div_ceil(2) {
+                // The color and the background are each chosen 43% of the
+                // time, and the spot color 13% of the time.
+                let cell = (random.next() * 2.3).floor() as u8;
+                row[x] = cell;
+                row[width - 1 - x] = cell;
+            }
+            cells.push(row);
+        }
+
+        BlockiesIconData {
+            size: SIZE,
+            color,
+            background,
+            spot_color,
+            cells,
+        }
+    }
+}
+
+/// The reference implementation's xorshift random number generator.
+///
+/// The reference implementation is written in JavaScript, where bitwise
+/// operators work on 32 bit signed integers, so this does its arithmetic on
+/// wrapping `i32`s.
+struct Xorshift([i32; 4]);
+
+impl Xorshift {
+    /// Seed the generator by spreading a hash like Java's `String.hashCode`
+    /// across its four words, one UTF-16 code unit at a time.
+    fn new(seed: &str) -> Self {
+        let mut state = [0i32; 4];
+        for (i, unit) in seed.encode_utf16().enumerate() {
+            let word = state[i % 4];
+            state[i % 4] = (word << 5).wrapping_sub(word).wrapping_add(unit as i32);
+        }
+        Xorshift(state)
+    }
+
+    /// A number from 0 up to, but not including, 1.
+    fn next(&mut self) -> f64 {
+        let state = &mut self.0;
+        let t = state[0] ^ (state[0] << 11);
+        state[0] = state[1];
+        state[1] = state[2];
+        state[2] = state[3];
+        state[3] = state[3] ^ (state[3] >> 19) ^ t ^ (t >> 8);
+        (state[3] as u32) as f64 / 2_147_483_648.0
+    }
+
+    /// A color, with lightness on a bell curve around 50%.
+    fn color(&mut self) -> BlockiesColor {
+        let hue = (self.next() * 360.0).floor() as u16;
+        let saturation = self.next() * 60.0 + 40.0;
+        let lightness = (self.next() + self.next() + self.next() + self.next()) * 25.0;
+        BlockiesColor {
+            hue,
+            saturation,
+            lightness,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use SeedKey;
+
+    /// The output of the reference implementation, blockies.js, for `seed`:
+    /// its three colors, and one string of cells for each row.
+    struct Vector {
+        seed: &'static str,
+        color: &'static str,
+        background: &'static str,
+        spot_color: &'static str,
+        rows: [&'static str; 8],
+    }
+
+    const VECTORS: &[Vector] = &[
+        Vector {
+            seed: "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359",
+            color: "hsl(40,95.56087048724294%,53.07253532810137%)",
+            background: "hsl(62,84.17102983221412%,56.51313749840483%)",
+            spot_color: "hsl(244,90.81825148314238%,69.22265975736082%)",
+            rows: [
+                "10000001", "00000000", "11000011", "12111121", "01011010", "02122120", "02000020",
+                "10211201",
+            ],
+        },
+        Vector {
+            seed: "0x5aaeb6053f3e94c9b9a09f33669435e7ef1beaed",
+            color: "hsl(222,80.46593819744885%,37.79142468702048%)",
+            background: "hsl(160,99.25892420113087%,52.513964427635074%)",
+            spot_color: "hsl(210,40.28361354023218%,42.68841225421056%)",
+            rows: [
+                "22111122", "01022010", "01100110", "00111100", "00000000", "01211210", "00122100",
+                "10100101",
+            ],
+        },
+        // The same address with its checksum capitalization.
+        Vector {
+            seed: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            color: "hsl(244,76.86842918395996%,39.39564910251647%)",
+            background: "hsl(296,56.29286986775696%,34.419715544208884%)",
+            spot_color: "hsl(43,58.76168238930404%,55.515767994802445%)",
+            rows: [
+                "01100110", "10022001", "11000011", "11100111", "10111101", "00111100", "00011000",
+                "01011010",
+            ],
+        },
+        // Characters outside the BMP count as two UTF-16 code units.
+        Vector {
+            seed: "🐶日本",
+            color: "hsl(19,40.05879142321646%,23.489971121307462%)",
+            background: "hsl(253,51.07052900828421%,29.347857018001378%)",
+            spot_color: "hsl(9,42.933743027970195%,64.75633479421958%)",
+            rows: [
+                "00122100", "10211201", "11200211", "02122120", "21011012", "21111112", "00100100",
+                "00111100",
+            ],
+        },
+    ];
+
+    #[test]
+    fn test_reference_vectors() {
+        for vector in VECTORS {
+            let icon = BlockiesIconData::from_input(vector.seed).unwrap();
+            assert_eq!(icon.color.css_color(), vector.color, "{}", vector.seed);
+            assert_eq!(
+                icon.background.css_color(),
+                vector.background,
+                "{}",
+                vector.seed
+            );
+            assert_eq!(
+                icon.spot_color.css_color(),
+                vector.spot_color,
+                "{}",
+                vector.seed
+            );
+            let rows: Vec<String> = icon
+                .cells
+                .iter()
+                .map(|row| row.iter().map(|c| c.to_string()).collect())
+                .collect();
+            assert_eq!(rows, vector.rows, "{}", vector.seed);
+        }
+    }
+
+    #[test]
+    fn test_to_rgb() {
+        let icon = BlockiesIconData::from_input(VECTORS[0].seed).unwrap();
+        assert_eq!(
+            icon.color.to_rgb(),
+            Color {
+                r: 250,
+                g: 173,
+                b: 21
+            }
+        );
+        assert_eq!(
+            icon.background.to_rgb(),
+            Color {
+                r: 231,
+                g: 237,
+                b: 51
+            }
+        );
+        assert_eq!(
+            icon.spot_color.to_rgb(),
+            Color {
+                r: 115,
+                g: 105,
+                b: 248
+            }
+        );
+
+        let grey = BlockiesColor {
+            hue: 0,
+            saturation: 0.0,
+            lightness: 50.0,
+        };
+        assert_eq!(
+            grey.to_rgb(),
+            Color {
+                r: 128,
+                g: 128,
+                b: 128
+            }
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let icon = BlockiesIconData::from_input(VECTORS[0].seed).unwrap();
+        let json = serde_json::to_string(&icon).unwrap();
+        assert!(json.contains(&format!(r#""color":"{}""#, VECTORS[0].color)));
+        assert_eq!(
+            serde_json::from_str::<BlockiesIconData>(&json).unwrap(),
+            icon
+        );
+        assert!(serde_json::from_str::<BlockiesColor>(r#""rgb(1,2,3)""#).is_err());
+        assert!(serde_json::from_str::<BlockiesColor>(r#""hsl(1,2%,3%,4%)""#).is_err());
+    }
+
+    #[test]
+    fn test_bytes_and_options() {
+        let seed = VECTORS[0].seed;
+        let options = GenerationOptions::default();
+        assert_eq!(
+            BlockiesIconData::from_bytes_with_options(seed.as_bytes(), &options).unwrap(),
+            BlockiesIconData::from_input(seed).unwrap()
+        );
+        assert!(BlockiesIconData::from_bytes_with_options(&[0xff], &options).is_err());
+        assert!(BlockiesIconData::from_digest_with_options(&[0; 64], &options).is_err());
+
+        let keyed = GenerationOptions {
+            key: Some(SeedKey::new("secret").unwrap()),
+            ..GenerationOptions::default()
+        };
+        match BlockiesIconData::from_bytes_with_options(seed.as_bytes(), &keyed) {
+            Err(Error::SeedKey(_)) => (),
+            other => panic!("expected a seed key error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rendering() {
+        let icon = BlockiesIconData::from_input(VECTORS[0].seed).unwrap();
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 8 8""#));
+        assert!(svg.contains(r#"fill="hsl(62,84.17102983221412%,56.51313749840483%)""#));
+
+        // At 80 pixels, each cell is 10 pixels. The first row is a cell in
+        // the color at each end, with the background between them.
+        let pixmap = tiny_skia::Pixmap::decode_png(&icon.to_png(80).unwrap()).unwrap();
+        for &(x, y, expected) in &[
+            (5, 5, icon.color),
+            (45, 5, icon.background),
+            (75, 5, icon.color),
+            (15, 35, icon.spot_color),
+        ] {
+            let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+            let rgb = expected.to_rgb();
+            assert_eq!(
+                (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()),
+                (rgb.r, rgb.g, rgb.b, 255),
+                "pixel at {}, {}",
+                x,
+                y
+            );
+        }
+        assert!(icon.to_webp(80).is_ok());
+
+        let invalid = BlockiesIconData {
+            cells: vec![vec![3; 8]; 8],
+            ..icon.clone()
+        };
+        assert!(invalid.to_svg(&RenderOptions::default()).is_err());
+        let ragged = BlockiesIconData {
+            cells: vec![vec![0; 8]],
+            ..icon
+        };
+        assert!(ragged.to_png(80).is_err());
+    }
+}
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use super::Color;
+use super::{Color, EmojiCategory};
 
 lazy_static! {
    /// Derived from <https://github.com/FirefoxUX/design-tokens/blob/29dc1033677c2b4721817b1d61b53034d0deea74/photon-colors/photon-colors.json>
@@ -63,12 +63,6 @@ lazy_static! {
         map
     };
 
-    pub static ref COLORS: Vec<Color> = {
-        let mut keys: Vec<&&'static str> = COLOR_MAP.keys().collect();
-        keys.sort();
-        keys.into_iter().map(|m| COLOR_MAP.get(m).unwrap().clone()).collect()
-    };
-
     pub static ref EMOJIS: Vec<char> = vec![
         '😄', '😃', '😀', '😊', '😉', '😍', '😘', '😚', '😗', '😙', '😜', '😝', '😛',
         '😳', '😁', '😔', '😌', '😒', '😞', '😣', '😢', '😂', '😭', '😪', '😥', '😰',
@@ -103,4 +97,61 @@ lazy_static! {
         '🚑', '🚐', '🚲', '🚜', '💈', '🚦', '🚧', '🏮', '🎰', '🗿', '🎪', '🎭', '📍',
         '🚩', '💯',
     ];
+
+    /// The category of each emoji in `EMOJIS`.
+    pub static ref EMOJI_CATEGORIES: Vec<(EmojiCategory, Vec<char>)> = vec![
+        (EmojiCategory::Faces, vec![
+            '😄', '😃', '😀', '😊', '😉', '😍', '😘', '😚', '😗', '😙', '😜', '😝', '😛',
+            '😳', '😁', '😔', '😌', '😒', '😞', '😣', '😢', '😂', '😭', '😪', '😥', '😰',
+            '😅', '😓', '😨', '😱', '😠', '😡', '😤', '😖', '😆', '😋', '😷', '😎', '😴',
+            '😵', '😲', '😟', '😦', '😧', '😈', '👿', '😮', '😬', '😐', '😯', '😶', '😇',
+            '😏', '😑', '😺', '😻', '😽', '😼', '🙀', '😿', '😹', '😾', '👹', '👺', '🙈',
+            '🙉', '🙊', '💀', '👽', '💩', '👻', '👾',
+        ]),
+        (EmojiCategory::People, vec![
+            '👼', '👂', '👀', '👃', '👅', '👄', '👍', '👎', '👌', '👊', '✊', '👋', '✋',
+            '👐', '👆', '🙌', '🙏', '👏', '💪', '💃', '👣',
+        ]),
+        (EmojiCategory::Animals, vec![
+            '🐶', '🐺', '🐱', '🐭', '🐹', '🐰', '🐸', '🐯', '🐨', '🐻', '🐷', '🐽', '🐮',
+            '🐗', '🐵', '🐒', '🐴', '🐑', '🐘', '🐼', '🐧', '🐦', '🐤', '🐥', '🐣', '🐔',
+            '🐍', '🐢', '🐛', '🐝', '🐜', '🐞', '🐌', '🐙', '🐚', '🐠', '🐟', '🐬', '🐳',
+            '🐋', '🐄', '🐏', '🐀', '🐃', '🐅', '🐇', '🐉', '🐎', '🐐', '🐓', '🐕', '🐖',
+            '🐁', '🐂', '🐲', '🐡', '🐊', '🐫', '🐪', '🐆', '🐈', '🐩', '🐾',
+        ]),
+        (EmojiCategory::Nature, vec![
+            '💐', '🌸', '🌷', '🍀', '🌹', '🌻', '🌺', '🍁', '🍃', '🍂', '🌿', '🌾', '🍄',
+            '🌵', '🌴', '🌲', '🌳', '🌰', '🌱', '🌼', '🌞', '🌝', '🌚', '🌜', '🌛', '🌙',
+            '🌍', '🌎', '🌏', '⭐', '⛅', '⛄', '🌀', '🔥', '✨', '🌟', '💫', '💥', '💦',
+            '💧',
+        ]),
+        (EmojiCategory::Food, vec![
+            '🍵', '🍶', '🍼', '🍺', '🍻', '🍸', '🍹', '🍷', '🍴', '🍕', '🍔', '🍟', '🍗',
+            '🍤', '🍞', '🍩', '🍮', '🍦', '🍨', '🍧', '🎂', '🍰', '🍪', '🍫', '🍬', '🍭',
+            '🍯', '🍎', '🍏', '🍊', '🍋', '🍒', '🍇', '🍉', '🍓', '🍑', '🍌', '🍐', '🍍',
+            '🍆', '🍅', '🌽',
+        ]),
+        (EmojiCategory::Activities, vec![
+            '🎏', '🎃', '🎄', '🎁', '🎋', '🎉', '🎈', '🎨', '🎬', '🎤', '🎵', '🎹', '🎻',
+            '🎺', '🎷', '🎸', '🎮', '🃏', '🎲', '🎯', '🏈', '🏀', '⚽', '🎾', '🎱', '🏉',
+            '🎳', '⛳', '🚴', '🏁', '🏇', '🏆', '🎿', '🏂', '🏄', '🎣', '🎰', '🎪', '🎭',
+        ]),
+        (EmojiCategory::Travel, vec![
+            '🏠', '🏡', '⛵', '🚤', '🚣', '🚀', '🚁', '🚂', '🚎', '🚌', '🚍', '🚙', '🚘',
+            '🚗', '🚕', '🚖', '🚛', '🚚', '🚨', '🚓', '🚔', '🚒', '🚑', '🚐', '🚲', '🚜',
+            '🚦', '🚧', '🏮', '💈', '🗿', '🌐',
+        ]),
+        (EmojiCategory::Objects, vec![
+            '🎩', '👑', '👒', '👟', '👞', '👡', '👠', '👢', '💼', '👜', '👝', '👛', '👓',
+            '🎀', '🌂', '💄', '💍', '💎', '🎒', '🎓', '🔮', '🎥', '📷', '📹', '📼', '💿',
+            '📀', '💽', '💾', '💻', '📱', '📞', '📟', '📠', '📡', '📺', '📻', '🔊', '🔔',
+            '📢', '⏳', '⏰', '🔓', '🔒', '🔏', '🔐', '🔑', '🔎', '💡', '🔦', '🔌', '🔋',
+            '🔍', '🛁', '🚿', '🚽', '🔧', '🔨', '🚪', '💣', '🔫', '🔪', '💊', '💉', '💰',
+            '💸', '📨', '📬', '📌', '📎', '📕', '📓', '📚', '📖', '🔬', '🔭', '📍',
+        ]),
+        (EmojiCategory::Symbols, vec![
+            '💛', '💙', '💜', '💚', '💔', '💗', '💓', '💕', '💖', '💞', '💘', '💌', '💋',
+            '💝', '💯', '💤', '🔆', '🔅', '🚩',
+        ]),
+    ];
 }
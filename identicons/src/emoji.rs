@@ -0,0 +1,457 @@
+//! Vector artwork for emoji, so they can be drawn without an emoji font.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// The Twemoji SVG for each emoji in `data::EMOJIS`.
+static TWEMOJI: OnceLock<HashMap<char, &'static str>> = OnceLock::new();
+
+fn twemoji() -> &'static HashMap<char, &'static str> {
+    TWEMOJI.get_or_init(|| {
+        vec![
+            ('😄', include_str!("twemoji/1f604.svg")),
+            ('😃', include_str!("twemoji/1f603.svg")),
+            ('😀', include_str!("twemoji/1f600.svg")),
+            ('😊', include_str!("twemoji/1f60a.svg")),
+            ('😉', include_str!("twemoji/1f609.svg")),
+            ('😍', include_str!("twemoji/1f60d.svg")),
+            ('😘', include_str!("twemoji/1f618.svg")),
+            ('😚', include_str!("twemoji/1f61a.svg")),
+            ('😗', include_str!("twemoji/1f617.svg")),
+            ('😙', include_str!("twemoji/1f619.svg")),
+            ('😜', include_str!("twemoji/1f61c.svg")),
+            ('😝', include_str!("twemoji/1f61d.svg")),
+            ('😛', include_str!("twemoji/1f61b.svg")),
+            ('😳', include_str!("twemoji/1f633.svg")),
+            ('😁', include_str!("twemoji/1f601.svg")),
+            ('😔', include_str!("twemoji/1f614.svg")),
+            ('😌', include_str!("twemoji/1f60c.svg")),
+            ('😒', include_str!("twemoji/1f612.svg")),
+            ('😞', include_str!("twemoji/1f61e.svg")),
+            ('😣', include_str!("twemoji/1f623.svg")),
+            ('😢', include_str!("twemoji/1f622.svg")),
+            ('😂', include_str!("twemoji/1f602.svg")),
+            ('😭', include_str!("twemoji/1f62d.svg")),
+            ('😪', include_str!("twemoji/1f62a.svg")),
+            ('😥', include_str!("twemoji/1f625.svg")),
+            ('😰', include_str!("twemoji/1f630.svg")),
+            ('😅', include_str!("twemoji/1f605.svg")),
+            ('😓', include_str!("twemoji/1f613.svg")),
+            ('😨', include_str!("twemoji/1f628.svg")),
+            ('😱', include_str!("twemoji/1f631.svg")),
+            ('😠', include_str!("twemoji/1f620.svg")),
+            ('😡', include_str!("twemoji/1f621.svg")),
+            ('😤', include_str!("twemoji/1f624.svg")),
+            ('😖', include_str!("twemoji/1f616.svg")),
+            ('😆', include_str!("twemoji/1f606.svg")),
+            ('😋', include_str!("twemoji/1f60b.svg")),
+            ('😷', include_str!("twemoji/1f637.svg")),
+            ('😎', include_str!("twemoji/1f60e.svg")),
+            ('😴', include_str!("twemoji/1f634.svg")),
+            ('😵', include_str!("twemoji/1f635.svg")),
+            ('😲', include_str!("twemoji/1f632.svg")),
+            ('😟', include_str!("twemoji/1f61f.svg")),
+            ('😦', include_str!("twemoji/1f626.svg")),
+            ('😧', include_str!("twemoji/1f627.svg")),
+            ('😈', include_str!("twemoji/1f608.svg")),
+            ('👿', include_str!("twemoji/1f47f.svg")),
+            ('😮', include_str!("twemoji/1f62e.svg")),
+            ('😬', include_str!("twemoji/1f62c.svg")),
+            ('😐', include_str!("twemoji/1f610.svg")),
+            ('😯', include_str!("twemoji/1f62f.svg")),
+            ('😶', include_str!("twemoji/1f636.svg")),
+            ('😇', include_str!("twemoji/1f607.svg")),
+            ('😏', include_str!("twemoji/1f60f.svg")),
+            ('😑', include_str!("twemoji/1f611.svg")),
+            ('👼', include_str!("twemoji/1f47c.svg")),
+            ('😺', include_str!("twemoji/1f63a.svg")),
+            ('😻', include_str!("twemoji/1f63b.svg")),
+            ('😽', include_str!("twemoji/1f63d.svg")),
+            ('😼', include_str!("twemoji/1f63c.svg")),
+            ('🙀', include_str!("twemoji/1f640.svg")),
+            ('😿', include_str!("twemoji/1f63f.svg")),
+            ('😹', include_str!("twemoji/1f639.svg")),
+            ('😾', include_str!("twemoji/1f63e.svg")),
+            ('👹', include_str!("twemoji/1f479.svg")),
+            ('👺', include_str!("twemoji/1f47a.svg")),
+            ('🙈', include_str!("twemoji/1f648.svg")),
+            ('🙉', include_str!("twemoji/1f649.svg")),
+            ('🙊', include_str!("twemoji/1f64a.svg")),
+            ('💀', include_str!("twemoji/1f480.svg")),
+            ('👽', include_str!("twemoji/1f47d.svg")),
+            ('💩', include_str!("twemoji/1f4a9.svg")),
+            ('🔥', include_str!("twemoji/1f525.svg")),
+            ('✨', include_str!("twemoji/2728.svg")),
+            ('🌟', include_str!("twemoji/1f31f.svg")),
+            ('💫', include_str!("twemoji/1f4ab.svg")),
+            ('💥', include_str!("twemoji/1f4a5.svg")),
+            ('💦', include_str!("twemoji/1f4a6.svg")),
+            ('💧', include_str!("twemoji/1f4a7.svg")),
+            ('💤', include_str!("twemoji/1f4a4.svg")),
+            ('👂', include_str!("twemoji/1f442.svg")),
+            ('👀', include_str!("twemoji/1f440.svg")),
+            ('👃', include_str!("twemoji/1f443.svg")),
+            ('👅', include_str!("twemoji/1f445.svg")),
+            ('👄', include_str!("twemoji/1f444.svg")),
+            ('👍', include_str!("twemoji/1f44d.svg")),
+            ('👎', include_str!("twemoji/1f44e.svg")),
+            ('👌', include_str!("twemoji/1f44c.svg")),
+            ('👊', include_str!("twemoji/1f44a.svg")),
+            ('✊', include_str!("twemoji/270a.svg")),
+            ('👋', include_str!("twemoji/1f44b.svg")),
+            ('✋', include_str!("twemoji/270b.svg")),
+            ('👐', include_str!("twemoji/1f450.svg")),
+            ('👆', include_str!("twemoji/1f446.svg")),
+            ('🙌', include_str!("twemoji/1f64c.svg")),
+            ('🙏', include_str!("twemoji/1f64f.svg")),
+            ('👏', include_str!("twemoji/1f44f.svg")),
+            ('💪', include_str!("twemoji/1f4aa.svg")),
+            ('💃', include_str!("twemoji/1f483.svg")),
+            ('🎩', include_str!("twemoji/1f3a9.svg")),
+            ('👑', include_str!("twemoji/1f451.svg")),
+            ('👒', include_str!("twemoji/1f452.svg")),
+            ('👟', include_str!("twemoji/1f45f.svg")),
+            ('👞', include_str!("twemoji/1f45e.svg")),
+            ('👡', include_str!("twemoji/1f461.svg")),
+            ('👠', include_str!("twemoji/1f460.svg")),
+            ('👢', include_str!("twemoji/1f462.svg")),
+            ('💼', include_str!("twemoji/1f4bc.svg")),
+            ('👜', include_str!("twemoji/1f45c.svg")),
+            ('👝', include_str!("twemoji/1f45d.svg")),
+            ('👛', include_str!("twemoji/1f45b.svg")),
+            ('👓', include_str!("twemoji/1f453.svg")),
+            ('🎀', include_str!("twemoji/1f380.svg")),
+            ('🌂', include_str!("twemoji/1f302.svg")),
+            ('💄', include_str!("twemoji/1f484.svg")),
+            ('💛', include_str!("twemoji/1f49b.svg")),
+            ('💙', include_str!("twemoji/1f499.svg")),
+            ('💜', include_str!("twemoji/1f49c.svg")),
+            ('💚', include_str!("twemoji/1f49a.svg")),
+            ('💔', include_str!("twemoji/1f494.svg")),
+            ('💗', include_str!("twemoji/1f497.svg")),
+            ('💓', include_str!("twemoji/1f493.svg")),
+            ('💕', include_str!("twemoji/1f495.svg")),
+            ('💖', include_str!("twemoji/1f496.svg")),
+            ('💞', include_str!("twemoji/1f49e.svg")),
+            ('💘', include_str!("twemoji/1f498.svg")),
+            ('💌', include_str!("twemoji/1f48c.svg")),
+            ('💋', include_str!("twemoji/1f48b.svg")),
+            ('💍', include_str!("twemoji/1f48d.svg")),
+            ('💎', include_str!("twemoji/1f48e.svg")),
+            ('👣', include_str!("twemoji/1f463.svg")),
+            ('🐶', include_str!("twemoji/1f436.svg")),
+            ('🐺', include_str!("twemoji/1f43a.svg")),
+            ('🐱', include_str!("twemoji/1f431.svg")),
+            ('🐭', include_str!("twemoji/1f42d.svg")),
+            ('🐹', include_str!("twemoji/1f439.svg")),
+            ('🐰', include_str!("twemoji/1f430.svg")),
+            ('🐸', include_str!("twemoji/1f438.svg")),
+            ('🐯', include_str!("twemoji/1f42f.svg")),
+            ('🐨', include_str!("twemoji/1f428.svg")),
+            ('🐻', include_str!("twemoji/1f43b.svg")),
+            ('🐷', include_str!("twemoji/1f437.svg")),
+            ('🐽', include_str!("twemoji/1f43d.svg")),
+            ('🐮', include_str!("twemoji/1f42e.svg")),
+            ('🐗', include_str!("twemoji/1f417.svg")),
+            ('🐵', include_str!("twemoji/1f435.svg")),
+            ('🐒', include_str!("twemoji/1f412.svg")),
+            ('🐴', include_str!("twemoji/1f434.svg")),
+            ('🐑', include_str!("twemoji/1f411.svg")),
+            ('🐘', include_str!("twemoji/1f418.svg")),
+            ('🐼', include_str!("twemoji/1f43c.svg")),
+            ('🐧', include_str!("twemoji/1f427.svg")),
+            ('🐦', include_str!("twemoji/1f426.svg")),
+            ('🐤', include_str!("twemoji/1f424.svg")),
+            ('🐥', include_str!("twemoji/1f425.svg")),
+            ('🐣', include_str!("twemoji/1f423.svg")),
+            ('🐔', include_str!("twemoji/1f414.svg")),
+            ('🐍', include_str!("twemoji/1f40d.svg")),
+            ('🐢', include_str!("twemoji/1f422.svg")),
+            ('🐛', include_str!("twemoji/1f41b.svg")),
+            ('🐝', include_str!("twemoji/1f41d.svg")),
+            ('🐜', include_str!("twemoji/1f41c.svg")),
+            ('🐞', include_str!("twemoji/1f41e.svg")),
+            ('🐌', include_str!("twemoji/1f40c.svg")),
+            ('🐙', include_str!("twemoji/1f419.svg")),
+            ('🐚', include_str!("twemoji/1f41a.svg")),
+            ('🐠', include_str!("twemoji/1f420.svg")),
+            ('🐟', include_str!("twemoji/1f41f.svg")),
+            ('🐬', include_str!("twemoji/1f42c.svg")),
+            ('🐳', include_str!("twemoji/1f433.svg")),
+            ('🐋', include_str!("twemoji/1f40b.svg")),
+            ('🐄', include_str!("twemoji/1f404.svg")),
+            ('🐏', include_str!("twemoji/1f40f.svg")),
+            ('🐀', include_str!("twemoji/1f400.svg")),
+            ('🐃', include_str!("twemoji/1f403.svg")),
+            ('🐅', include_str!("twemoji/1f405.svg")),
+            ('🐇', include_str!("twemoji/1f407.svg")),
+            ('🐉', include_str!("twemoji/1f409.svg")),
+            ('🐎', include_str!("twemoji/1f40e.svg")),
+            ('🐐', include_str!("twemoji/1f410.svg")),
+            ('🐓', include_str!("twemoji/1f413.svg")),
+            ('🐕', include_str!("twemoji/1f415.svg")),
+            ('🐖', include_str!("twemoji/1f416.svg")),
+            ('🐁', include_str!("twemoji/1f401.svg")),
+            ('🐂', include_str!("twemoji/1f402.svg")),
+            ('🐲', include_str!("twemoji/1f432.svg")),
+            ('🐡', include_str!("twemoji/1f421.svg")),
+            ('🐊', include_str!("twemoji/1f40a.svg")),
+            ('🐫', include_str!("twemoji/1f42b.svg")),
+            ('🐪', include_str!("twemoji/1f42a.svg")),
+            ('🐆', include_str!("twemoji/1f406.svg")),
+            ('🐈', include_str!("twemoji/1f408.svg")),
+            ('🐩', include_str!("twemoji/1f429.svg")),
+            ('🐾', include_str!("twemoji/1f43e.svg")),
+            ('💐', include_str!("twemoji/1f490.svg")),
+            ('🌸', include_str!("twemoji/1f338.svg")),
+            ('🌷', include_str!("twemoji/1f337.svg")),
+            ('🍀', include_str!("twemoji/1f340.svg")),
+            ('🌹', include_str!("twemoji/1f339.svg")),
+            ('🌻', include_str!("twemoji/1f33b.svg")),
+            ('🌺', include_str!("twemoji/1f33a.svg")),
+            ('🍁', include_str!("twemoji/1f341.svg")),
+            ('🍃', include_str!("twemoji/1f343.svg")),
+            ('🍂', include_str!("twemoji/1f342.svg")),
+            ('🌿', include_str!("twemoji/1f33f.svg")),
+            ('🌾', include_str!("twemoji/1f33e.svg")),
+            ('🍄', include_str!("twemoji/1f344.svg")),
+            ('🌵', include_str!("twemoji/1f335.svg")),
+            ('🌴', include_str!("twemoji/1f334.svg")),
+            ('🌲', include_str!("twemoji/1f332.svg")),
+            ('🌳', include_str!("twemoji/1f333.svg")),
+            ('🌰', include_str!("twemoji/1f330.svg")),
+            ('🌱', include_str!("twemoji/1f331.svg")),
+            ('🌼', include_str!("twemoji/1f33c.svg")),
+            ('🌐', include_str!("twemoji/1f310.svg")),
+            ('🌞', include_str!("twemoji/1f31e.svg")),
+            ('🌝', include_str!("twemoji/1f31d.svg")),
+            ('🌚', include_str!("twemoji/1f31a.svg")),
+            ('🌜', include_str!("twemoji/1f31c.svg")),
+            ('🌛', include_str!("twemoji/1f31b.svg")),
+            ('🌙', include_str!("twemoji/1f319.svg")),
+            ('🌍', include_str!("twemoji/1f30d.svg")),
+            ('🌎', include_str!("twemoji/1f30e.svg")),
+            ('🌏', include_str!("twemoji/1f30f.svg")),
+            ('⭐', include_str!("twemoji/2b50.svg")),
+            ('⛅', include_str!("twemoji/26c5.svg")),
+            ('⛄', include_str!("twemoji/26c4.svg")),
+            ('🌀', include_str!("twemoji/1f300.svg")),
+            ('💝', include_str!("twemoji/1f49d.svg")),
+            ('🎒', include_str!("twemoji/1f392.svg")),
+            ('🎓', include_str!("twemoji/1f393.svg")),
+            ('🎏', include_str!("twemoji/1f38f.svg")),
+            ('🎃', include_str!("twemoji/1f383.svg")),
+            ('👻', include_str!("twemoji/1f47b.svg")),
+            ('🎄', include_str!("twemoji/1f384.svg")),
+            ('🎁', include_str!("twemoji/1f381.svg")),
+            ('🎋', include_str!("twemoji/1f38b.svg")),
+            ('🎉', include_str!("twemoji/1f389.svg")),
+            ('🎈', include_str!("twemoji/1f388.svg")),
+            ('🔮', include_str!("twemoji/1f52e.svg")),
+            ('🎥', include_str!("twemoji/1f3a5.svg")),
+            ('📷', include_str!("twemoji/1f4f7.svg")),
+            ('📹', include_str!("twemoji/1f4f9.svg")),
+            ('📼', include_str!("twemoji/1f4fc.svg")),
+            ('💿', include_str!("twemoji/1f4bf.svg")),
+            ('📀', include_str!("twemoji/1f4c0.svg")),
+            ('💽', include_str!("twemoji/1f4bd.svg")),
+            ('💾', include_str!("twemoji/1f4be.svg")),
+            ('💻', include_str!("twemoji/1f4bb.svg")),
+            ('📱', include_str!("twemoji/1f4f1.svg")),
+            ('📞', include_str!("twemoji/1f4de.svg")),
+            ('📟', include_str!("twemoji/1f4df.svg")),
+            ('📠', include_str!("twemoji/1f4e0.svg")),
+            ('📡', include_str!("twemoji/1f4e1.svg")),
+            ('📺', include_str!("twemoji/1f4fa.svg")),
+            ('📻', include_str!("twemoji/1f4fb.svg")),
+            ('🔊', include_str!("twemoji/1f50a.svg")),
+            ('🔔', include_str!("twemoji/1f514.svg")),
+            ('📢', include_str!("twemoji/1f4e2.svg")),
+            ('⏳', include_str!("twemoji/23f3.svg")),
+            ('⏰', include_str!("twemoji/23f0.svg")),
+            ('🔓', include_str!("twemoji/1f513.svg")),
+            ('🔒', include_str!("twemoji/1f512.svg")),
+            ('🔏', include_str!("twemoji/1f50f.svg")),
+            ('🔐', include_str!("twemoji/1f510.svg")),
+            ('🔑', include_str!("twemoji/1f511.svg")),
+            ('🔎', include_str!("twemoji/1f50e.svg")),
+            ('💡', include_str!("twemoji/1f4a1.svg")),
+            ('🔦', include_str!("twemoji/1f526.svg")),
+            ('🔆', include_str!("twemoji/1f506.svg")),
+            ('🔅', include_str!("twemoji/1f505.svg")),
+            ('🔌', include_str!("twemoji/1f50c.svg")),
+            ('🔋', include_str!("twemoji/1f50b.svg")),
+            ('🔍', include_str!("twemoji/1f50d.svg")),
+            ('🛁', include_str!("twemoji/1f6c1.svg")),
+            ('🚿', include_str!("twemoji/1f6bf.svg")),
+            ('🚽', include_str!("twemoji/1f6bd.svg")),
+            ('🔧', include_str!("twemoji/1f527.svg")),
+            ('🔨', include_str!("twemoji/1f528.svg")),
+            ('🚪', include_str!("twemoji/1f6aa.svg")),
+            ('💣', include_str!("twemoji/1f4a3.svg")),
+            ('🔫', include_str!("twemoji/1f52b.svg")),
+            ('🔪', include_str!("twemoji/1f52a.svg")),
+            ('💊', include_str!("twemoji/1f48a.svg")),
+            ('💉', include_str!("twemoji/1f489.svg")),
+            ('💰', include_str!("twemoji/1f4b0.svg")),
+            ('💸', include_str!("twemoji/1f4b8.svg")),
+            ('📨', include_str!("twemoji/1f4e8.svg")),
+            ('📬', include_str!("twemoji/1f4ec.svg")),
+            ('📌', include_str!("twemoji/1f4cc.svg")),
+            ('📎', include_str!("twemoji/1f4ce.svg")),
+            ('📕', include_str!("twemoji/1f4d5.svg")),
+            ('📓', include_str!("twemoji/1f4d3.svg")),
+            ('📚', include_str!("twemoji/1f4da.svg")),
+            ('📖', include_str!("twemoji/1f4d6.svg")),
+            ('🔬', include_str!("twemoji/1f52c.svg")),
+            ('🔭', include_str!("twemoji/1f52d.svg")),
+            ('🎨', include_str!("twemoji/1f3a8.svg")),
+            ('🎬', include_str!("twemoji/1f3ac.svg")),
+            ('🎤', include_str!("twemoji/1f3a4.svg")),
+            ('🎵', include_str!("twemoji/1f3b5.svg")),
+            ('🎹', include_str!("twemoji/1f3b9.svg")),
+            ('🎻', include_str!("twemoji/1f3bb.svg")),
+            ('🎺', include_str!("twemoji/1f3ba.svg")),
+            ('🎷', include_str!("twemoji/1f3b7.svg")),
+            ('🎸', include_str!("twemoji/1f3b8.svg")),
+            ('👾', include_str!("twemoji/1f47e.svg")),
+            ('🎮', include_str!("twemoji/1f3ae.svg")),
+            ('🃏', include_str!("twemoji/1f0cf.svg")),
+            ('🎲', include_str!("twemoji/1f3b2.svg")),
+            ('🎯', include_str!("twemoji/1f3af.svg")),
+            ('🏈', include_str!("twemoji/1f3c8.svg")),
+            ('🏀', include_str!("twemoji/1f3c0.svg")),
+            ('⚽', include_str!("twemoji/26bd.svg")),
+            ('🎾', include_str!("twemoji/1f3be.svg")),
+            ('🎱', include_str!("twemoji/1f3b1.svg")),
+            ('🏉', include_str!("twemoji/1f3c9.svg")),
+            ('🎳', include_str!("twemoji/1f3b3.svg")),
+            ('⛳', include_str!("twemoji/26f3.svg")),
+            ('🚴', include_str!("twemoji/1f6b4.svg")),
+            ('🏁', include_str!("twemoji/1f3c1.svg")),
+            ('🏇', include_str!("twemoji/1f3c7.svg")),
+            ('🏆', include_str!("twemoji/1f3c6.svg")),
+            ('🎿', include_str!("twemoji/1f3bf.svg")),
+            ('🏂', include_str!("twemoji/1f3c2.svg")),
+            ('🏄', include_str!("twemoji/1f3c4.svg")),
+            ('🎣', include_str!("twemoji/1f3a3.svg")),
+            ('🍵', include_str!("twemoji/1f375.svg")),
+            ('🍶', include_str!("twemoji/1f376.svg")),
+            ('🍼', include_str!("twemoji/1f37c.svg")),
+            ('🍺', include_str!("twemoji/1f37a.svg")),
+            ('🍻', include_str!("twemoji/1f37b.svg")),
+            ('🍸', include_str!("twemoji/1f378.svg")),
+            ('🍹', include_str!("twemoji/1f379.svg")),
+            ('🍷', include_str!("twemoji/1f377.svg")),
+            ('🍴', include_str!("twemoji/1f374.svg")),
+            ('🍕', include_str!("twemoji/1f355.svg")),
+            ('🍔', include_str!("twemoji/1f354.svg")),
+            ('🍟', include_str!("twemoji/1f35f.svg")),
+            ('🍗', include_str!("twemoji/1f357.svg")),
+            ('🍤', include_str!("twemoji/1f364.svg")),
+            ('🍞', include_str!("twemoji/1f35e.svg")),
+            ('🍩', include_str!("twemoji/1f369.svg")),
+            ('🍮', include_str!("twemoji/1f36e.svg")),
+            ('🍦', include_str!("twemoji/1f366.svg")),
+            ('🍨', include_str!("twemoji/1f368.svg")),
+            ('🍧', include_str!("twemoji/1f367.svg")),
+            ('🎂', include_str!("twemoji/1f382.svg")),
+            ('🍰', include_str!("twemoji/1f370.svg")),
+            ('🍪', include_str!("twemoji/1f36a.svg")),
+            ('🍫', include_str!("twemoji/1f36b.svg")),
+            ('🍬', include_str!("twemoji/1f36c.svg")),
+            ('🍭', include_str!("twemoji/1f36d.svg")),
+            ('🍯', include_str!("twemoji/1f36f.svg")),
+            ('🍎', include_str!("twemoji/1f34e.svg")),
+            ('🍏', include_str!("twemoji/1f34f.svg")),
+            ('🍊', include_str!("twemoji/1f34a.svg")),
+            ('🍋', include_str!("twemoji/1f34b.svg")),
+            ('🍒', include_str!("twemoji/1f352.svg")),
+            ('🍇', include_str!("twemoji/1f347.svg")),
+            ('🍉', include_str!("twemoji/1f349.svg")),
+            ('🍓', include_str!("twemoji/1f353.svg")),
+            ('🍑', include_str!("twemoji/1f351.svg")),
+            ('🍌', include_str!("twemoji/1f34c.svg")),
+            ('🍐', include_str!("twemoji/1f350.svg")),
+            ('🍍', include_str!("twemoji/1f34d.svg")),
+            ('🍆', include_str!("twemoji/1f346.svg")),
+            ('🍅', include_str!("twemoji/1f345.svg")),
+            ('🌽', include_str!("twemoji/1f33d.svg")),
+            ('🏠', include_str!("twemoji/1f3e0.svg")),
+            ('🏡', include_str!("twemoji/1f3e1.svg")),
+            ('⛵', include_str!("twemoji/26f5.svg")),
+            ('🚤', include_str!("twemoji/1f6a4.svg")),
+            ('🚣', include_str!("twemoji/1f6a3.svg")),
+            ('🚀', include_str!("twemoji/1f680.svg")),
+            ('🚁', include_str!("twemoji/1f681.svg")),
+            ('🚂', include_str!("twemoji/1f682.svg")),
+            ('🚎', include_str!("twemoji/1f68e.svg")),
+            ('🚌', include_str!("twemoji/1f68c.svg")),
+            ('🚍', include_str!("twemoji/1f68d.svg")),
+            ('🚙', include_str!("twemoji/1f699.svg")),
+            ('🚘', include_str!("twemoji/1f698.svg")),
+            ('🚗', include_str!("twemoji/1f697.svg")),
+            ('🚕', include_str!("twemoji/1f695.svg")),
+            ('🚖', include_str!("twemoji/1f696.svg")),
+            ('🚛', include_str!("twemoji/1f69b.svg")),
+            ('🚚', include_str!("twemoji/1f69a.svg")),
+            ('🚨', include_str!("twemoji/1f6a8.svg")),
+            ('🚓', include_str!("twemoji/1f693.svg")),
+            ('🚔', include_str!("twemoji/1f694.svg")),
+            ('🚒', include_str!("twemoji/1f692.svg")),
+            ('🚑', include_str!("twemoji/1f691.svg")),
+            ('🚐', include_str!("twemoji/1f690.svg")),
+            ('🚲', include_str!("twemoji/1f6b2.svg")),
+            ('🚜', include_str!("twemoji/1f69c.svg")),
+            ('💈', include_str!("twemoji/1f488.svg")),
+            ('🚦', include_str!("twemoji/1f6a6.svg")),
+            ('🚧', include_str!("twemoji/1f6a7.svg")),
+            ('🏮', include_str!("twemoji/1f3ee.svg")),
+            ('🎰', include_str!("twemoji/1f3b0.svg")),
+            ('🗿', include_str!("twemoji/1f5ff.svg")),
+            ('🎪', include_str!("twemoji/1f3aa.svg")),
+            ('🎭', include_str!("twemoji/1f3ad.svg")),
+            ('📍', include_str!("twemoji/1f4cd.svg")),
+            ('🚩', include_str!("twemoji/1f6a9.svg")),
+            ('💯', include_str!("twemoji/1f4af.svg")),
+        ]
+        .into_iter()
+        .collect()
+    })
+}
+
+/// The size of the square that Twemoji images are drawn in.
+pub const TWEMOJI_SIZE: f32 = 36.0;
+
+/// Get the SVG image of `emoji`, if there is one.
+pub fn svg(emoji: char) -> Option<&'static str> {
+    twemoji().get(&emoji).cloned()
+}
+
+/// Get the contents of the SVG image of `emoji`, without the root `<svg>`
+/// element, so that it can be placed into another image.
+pub fn svg_contents(emoji: char) -> Option<&'static str> {
+    svg(emoji).map(|svg| {
+        let start = svg.find('>').map_or(0, |i| i + 1);
+        let end = svg.rfind("</svg>").unwrap_or(svg.len());
+        &svg[start..end]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data;
+
+    #[test]
+    fn test_all_emoji_have_artwork() {
+        for emoji in data::EMOJIS.iter() {
+            let contents = match svg_contents(*emoji) {
+                Some(contents) => contents,
+                None => panic!("no artwork for {}", emoji),
+            };
+            assert!(!contents.contains("<svg"));
+            assert!(!contents.contains("id="));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+//! Sets of emoji that icons are generated from.
+
+use super::{data, Error};
+
+/// A group of related emoji in the standard set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EmojiCategory {
+    /// Smileys and other faces, like 😄, 😺 and 👽.
+    Faces,
+    /// Hands and other parts of people, like 👍 and 👀.
+    People,
+    /// Animals, like 🐶 and 🐙.
+    Animals,
+    /// Plants, weather and the sky, like 🌻 and ⛅.
+    Nature,
+    /// Food and drink, like 🍕 and 🍵.
+    Food,
+    /// Sports, games, music and celebrations, like ⚽ and 🎸.
+    Activities,
+    /// Vehicles and places, like 🚀 and 🏠.
+    Travel,
+    /// Things people use and wear, like 💡 and 👑.
+    Objects,
+    /// Hearts and other symbols, like 💛 and 💯.
+    Symbols,
+}
+
+impl EmojiCategory {
+    /// Every category, in the order they are listed here.
+    pub const ALL: [EmojiCategory; 9] = [
+        EmojiCategory::Faces,
+        EmojiCategory::People,
+        EmojiCategory::Animals,
+        EmojiCategory::Nature,
+        EmojiCategory::Food,
+        EmojiCategory::Activities,
+        EmojiCategory::Travel,
+        EmojiCategory::Objects,
+        EmojiCategory::Symbols,
+    ];
+
+    /// Find which category an emoji from the standard set belongs to.
+    pub fn of(emoji: char) -> Option<EmojiCategory> {
+        data::EMOJI_CATEGORIES
+            .iter()
+            .find(|(_, members)| members.contains(&emoji))
+            .map(|&(category, _)| category)
+    }
+}
+
+/// An ordered set of emoji for icons to be generated from.
+///
+/// Icons choose an emoji by its position in the set, so adding, removing or
+/// reordering emoji will change the icons generated for most seeds. The
+/// standard set is the default, and always keeps the same order.
+///
+///     # use identicons::{EmojiCategory, EmojiSet};
+///     let animals = EmojiSet::from_categories(&[EmojiCategory::Animals]).unwrap();
+///     assert!(animals.contains('🐙'));
+///
+///     let safe = EmojiSet::standard().excluding("💩🔫💣💉🍆💊🍺".chars()).unwrap();
+///     assert!(!safe.contains('🔫'));
+///
+///     let custom = EmojiSet::new("🌲🌳🌴".chars()).unwrap();
+///     assert_eq!(custom.emoji(), &['🌲', '🌳', '🌴']);
+///
+/// All emoji in the standard set have vector artwork. Emoji in other sets
+/// may not, which makes it an error to render them as PNGs or as SVGs with
+/// `EmojiStyle::Vector`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmojiSet {
+    emoji: Vec<char>,
+}
+
+impl EmojiSet {
+    /// Create a set of emoji, in the order given. Repeated emoji are only
+    /// included the first time they appear.
+    ///
+    /// Returns an error if there are no emoji.
+    pub fn new<I: IntoIterator<Item = char>>(emoji: I) -> Result<Self, Error> {
+        let mut unique: Vec<char> = Vec::new();
+        for e in emoji {
+            if !unique.contains(&e) {
+                unique.push(e);
+            }
+        }
+        if unique.is_empty() {
+            return Err(Error::EmojiSet(
+                "an emoji set needs at least one emoji".to_string(),
+            ));
+        }
+        Ok(EmojiSet { emoji: unique })
+    }
+
+    /// The emoji that icons are generated from by default.
+    pub fn standard() -> Self {
+        EmojiSet {
+            emoji: data::EMOJIS.clone(),
+        }
+    }
+
+    /// The emoji from the standard set that are in any of `categories`, in
+    /// their standard order.
+    pub fn from_categories(categories: &[EmojiCategory]) -> Result<Self, Error> {
+        EmojiSet::new(
+            data::EMOJIS
+                .iter()
+                .cloned()
+                .filter(|&e| match EmojiCategory::of(e) {
+                    Some(category) => categories.contains(&category),
+                    None => false,
+                }),
+        )
+    }
+
+    /// This set without any of `excluded`.
+    ///
+    /// Returns an error if that would leave no emoji.
+    pub fn excluding<I: IntoIterator<Item = char>>(&self, excluded: I) -> Result<Self, Error> {
+        let excluded: Vec<char> = excluded.into_iter().collect();
+        EmojiSet::new(self.emoji.iter().cloned().filter(|e| !excluded.contains(e)))
+    }
+
+    /// The emoji in this set, in order.
+    pub fn emoji(&self) -> &[char] {
+        &self.emoji
+    }
+
+    /// Is `emoji` in this set?
+    pub fn contains(&self, emoji: char) -> bool {
+        self.emoji.contains(&emoji)
+    }
+}
+
+impl Default for EmojiSet {
+    fn default() -> Self {
+        EmojiSet::standard()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_categories_cover_standard_set() {
+        for &emoji in data::EMOJIS.iter() {
+            let count = data::EMOJI_CATEGORIES
+                .iter()
+                .filter(|(_, members)| members.contains(&emoji))
+                .count();
+            assert_eq!(count, 1, "{} should be in exactly one category", emoji);
+        }
+        let categorized: usize = data::EMOJI_CATEGORIES.iter().map(|c| c.1.len()).sum();
+        assert_eq!(categorized, data::EMOJIS.len());
+
+        let all = EmojiSet::from_categories(&EmojiCategory::ALL).unwrap();
+        assert_eq!(all, EmojiSet::standard());
+    }
+
+    #[test]
+    fn test_from_categories() {
+        let set =
+            EmojiSet::from_categories(&[EmojiCategory::Food, EmojiCategory::Animals]).unwrap();
+        assert!(set.contains('🐶'));
+        assert!(set.contains('🍕'));
+        assert!(!set.contains('😄'));
+        // The standard order is kept, rather than the order of the categories.
+        assert_eq!(set.emoji()[0], '🐶');
+
+        assert!(EmojiSet::from_categories(&[]).is_err());
+    }
+
+    #[test]
+    fn test_new_and_excluding() {
+        let set = EmojiSet::new("🐶🐱🐶".chars()).unwrap();
+        assert_eq!(set.emoji(), &['🐶', '🐱']);
+        assert_eq!(set.excluding(vec!['🐶']).unwrap().emoji(), &['🐱']);
+        assert!(set.excluding("🐱🐶".chars()).is_err());
+        assert!(EmojiSet::new(vec![]).is_err());
+    }
+}
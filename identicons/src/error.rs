@@ -0,0 +1,107 @@
+//! Errors that can occur while generating or rendering identicons.
+
+use serde_json;
+use std::error::Error as StdError;
+use std::fmt;
+use tera;
+
+use super::RasterError;
+
+/// An error from generating or rendering an identicon.
+#[derive(Debug)]
+pub enum Error {
+    /// The genome ran out of entropy before the icon was fully generated.
+    GenomeExhausted {
+        /// How many bits of entropy the failed choice needed.
+        requested_bits: usize,
+        /// How many bits of entropy were left in the genome.
+        remaining_bits: usize,
+    },
+    /// A template failed to render.
+    ///
+    /// This holds the message from the template engine and its causes, since
+    /// the engine's own error type can't be shared between threads.
+    Render(String),
+    /// The icon's parameters don't describe an icon that can be drawn.
+    InvalidData(String),
+    /// The icon could not be serialized or deserialized.
+    Serialization(serde_json::Error),
+    /// The icon could not be rasterized.
+    Raster(RasterError),
+    /// A palette could not be loaded, or has no colors that work together.
+    Palette(String),
+    /// An emoji set could not be created, usually because it would be empty.
+    EmojiSet(String),
+    /// A key for seeding genomes could not be used, usually because it is
+    /// empty.
+    SeedKey(String),
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::GenomeExhausted { .. } => "the genome ran out of entropy",
+            Error::Render(_) => "could not render template",
+            Error::InvalidData(_) => "invalid icon data",
+            Error::Serialization(_) => "could not serialize icon",
+            Error::Raster(_) => "could not rasterize icon",
+            Error::Palette(_) => "unusable palette",
+            Error::EmojiSet(_) => "unusable emoji set",
+            Error::SeedKey(_) => "unusable seed key",
+        }
+    }
+
+    fn cause(&self) -> Option<&dyn StdError> {
+        match *self {
+            Error::GenomeExhausted { .. }
+            | Error::Render(_)
+            | Error::InvalidData(_)
+            | Error::Palette(_)
+            | Error::EmojiSet(_)
+            | Error::SeedKey(_) => None,
+            Error::Serialization(ref err) => Some(err),
+            Error::Raster(ref err) => Some(err),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::GenomeExhausted {
+                requested_bits,
+                remaining_bits,
+            } => write!(
+                fmt,
+                "the genome ran out of entropy: {} bits requested, {} bits left",
+                requested_bits, remaining_bits
+            ),
+            Error::Render(ref msg) => write!(fmt, "could not render template: {}", msg),
+            Error::InvalidData(ref msg) => write!(fmt, "invalid icon data: {}", msg),
+            Error::Serialization(ref err) => write!(fmt, "could not serialize icon: {}", err),
+            Error::Raster(ref err) => write!(fmt, "could not rasterize icon: {}", err),
+            Error::Palette(ref msg) => write!(fmt, "unusable palette: {}", msg),
+            Error::EmojiSet(ref msg) => write!(fmt, "unusable emoji set: {}", msg),
+            Error::SeedKey(ref msg) => write!(fmt, "unusable seed key: {}", msg),
+        }
+    }
+}
+
+impl From<tera::Error> for Error {
+    fn from(err: tera::Error) -> Self {
+        let messages: Vec<String> = err.iter().map(|e| e.to_string()).collect();
+        Error::Render(messages.join(": "))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Serialization(err)
+    }
+}
+
+impl From<RasterError> for Error {
+    fn from(err: RasterError) -> Self {
+        Error::Raster(err)
+    }
+}
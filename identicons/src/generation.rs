@@ -0,0 +1,158 @@
+//! Options that control how icons are generated from their seeds.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use super::genome::Genome;
+use super::{Color, EmojiSet, Error, Palette, ShieldSilhouette};
+
+/// Options for generating an icon.
+///
+/// The default options generate the same icons as earlier versions of this
+/// crate, so changing any of them will change the icon for a seed.
+///
+///     # use identicons::{GenerationOptions, Palette, ShieldIconData};
+///     let options = GenerationOptions {
+///         palette: Palette::from_toml(r##"
+///             navy = "#001f3f"
+///             gold = "#ffd700"
+///         "##).unwrap(),
+///         ..GenerationOptions::default()
+///     };
+///     let icon = ShieldIconData::from_input_with_options("example", &options).unwrap();
+///
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenerationOptions {
+    /// The colors to choose from.
+    pub palette: Palette,
+    /// How to decide which colors can be drawn on top of each other.
+    pub contrast: ContrastMode,
+    /// The emoji to choose from.
+    pub emoji: EmojiSet,
+    /// Text, such as someone's initials, to draw instead of the emoji. It
+    /// must be 1 to 3 characters long, counting each grapheme cluster as one
+    /// character.
+    ///
+    /// An emoji is still chosen, so the rest of the icon is the same as it
+    /// would be without text.
+    pub text: Option<String>,
+    /// A secret to key the hash of each seed with. Without the key, nobody
+    /// can work out which icon a seed has, or guess a seed from its icon.
+    ///
+    /// Icons generated with a key are unrelated to the icons generated
+    /// without one, or with a different key.
+    pub key: Option<SeedKey>,
+    /// The outline to give shields, instead of choosing one from the seed.
+    ///
+    /// The outline is still chosen, so the rest of the icon is the same as
+    /// it would be with the chosen outline, except that charges that don't
+    /// fit are left off. Only shields from version 4 on have a choice of
+    /// outline, so other icons ignore this.
+    pub silhouette: Option<ShieldSilhouette>,
+}
+
+impl GenerationOptions {
+    /// The genome to generate an icon for `seed` from.
+    pub(crate) fn genome(&self, seed: &[u8]) -> Genome {
+        match self.key {
+            Some(ref key) => Genome::via_hmac_sha512(&key.0, seed),
+            None => Genome::via_sha512(seed),
+        }
+    }
+
+    /// The genome to generate an icon for a precomputed `digest` from.
+    ///
+    /// The digest is used as is, so it can't be combined with a key. Key it
+    /// when computing it instead, by using HMAC-SHA512.
+    pub(crate) fn digest_genome(&self, digest: &[u8; 64]) -> Result<Genome, Error> {
+        if self.key.is_some() {
+            return Err(Error::SeedKey(
+                "a precomputed digest can't be keyed".to_string(),
+            ));
+        }
+        Ok(Genome::from_digest(digest))
+    }
+}
+
+/// A secret key for seeding icons with HMAC-SHA512 instead of SHA-512.
+///
+///     # use identicons::{GenerationOptions, SeedKey, ShieldIconData};
+///     let options = GenerationOptions {
+///         key: Some(SeedKey::new("correct horse battery staple").unwrap()),
+///         ..GenerationOptions::default()
+///     };
+///     let keyed = ShieldIconData::from_input_with_options("me@example.com", &options).unwrap();
+///
+/// The key is never included in `Debug` output, so that it doesn't end up in
+/// logs.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SeedKey(Vec<u8>);
+
+impl SeedKey {
+    /// Create a key from some secret bytes. Returns an error if there aren't
+    /// any.
+    pub fn new<T: Into<Vec<u8>>>(key: T) -> Result<Self, Error> {
+        let key = key.into();
+        if key.is_empty() {
+            return Err(Error::SeedKey("a key can't be empty".to_string()));
+        }
+        Ok(SeedKey(key))
+    }
+
+    /// Read a key from the file at `path`. Trailing whitespace, such as the
+    /// newline that editors add, isn't part of the key.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let mut key = fs::read(path)
+            .map_err(|err| Error::SeedKey(format!("couldn't read {}: {}", path.display(), err)))?;
+        while key.last().map(u8::is_ascii_whitespace) == Some(true) {
+            key.pop();
+        }
+        SeedKey::new(key)
+    }
+}
+
+impl fmt::Debug for SeedKey {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "SeedKey(..)")
+    }
+}
+
+/// How to decide if two colors contrast well enough to be used together,
+/// such as a shield's field and its pattern, or a shape's border and fill.
+///
+///     # use identicons::{ContrastMode, GenerationOptions, ShapeIconData};
+///     let options = GenerationOptions {
+///         contrast: ContrastMode::WCAG_AA,
+///         ..GenerationOptions::default()
+///     };
+///     let icon = ShapeIconData::from_input_with_options("example", &options).unwrap();
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ContrastMode {
+    /// Use `Color::contrasts_well`. This is what earlier versions of this
+    /// crate used, so it is the default.
+    #[default]
+    Legacy,
+    /// Require a WCAG 2 contrast ratio of at least this much, as measured by
+    /// `Color::contrast_ratio`.
+    MinimumRatio(f32),
+}
+
+impl ContrastMode {
+    /// The WCAG AA minimum for large text and graphics, 3:1.
+    pub const WCAG_AA_LARGE: ContrastMode = ContrastMode::MinimumRatio(3.0);
+    /// The WCAG AA minimum for text, 4.5:1.
+    pub const WCAG_AA: ContrastMode = ContrastMode::MinimumRatio(4.5);
+    /// The WCAG AAA minimum for text, 7:1.
+    pub const WCAG_AAA: ContrastMode = ContrastMode::MinimumRatio(7.0);
+
+    /// Do these two colors contrast well enough to be used together?
+    pub fn contrasts(&self, a: &Color, b: &Color) -> bool {
+        match *self {
+            ContrastMode::Legacy => a.contrasts_well(b),
+            ContrastMode::MinimumRatio(ratio) => a.contrast_ratio(b) >= ratio,
+        }
+    }
+}
@@ -1,37 +1,102 @@
 //! A way to convert an input into a series of choices in a unpredictable,
 //! determinstic way.
 
-use num::{BigUint, ToPrimitive};
+use hmac::{Hmac, Mac};
 use sha2::{Digest, Sha512};
-use std::ops::{Div, Rem};
 
-lazy_static! {
-    static ref MAX_512_BIT: BigUint = {
-        use num::{bigint::ToBigUint, pow::pow};
-        pow(2u32.to_biguint().unwrap(), 512)
-    };
+use super::{Error, GenerationOptions};
+
+/// The number of 64 bit limbs in a `Wide`. A SHA-512 hash fits in 8, and one
+/// more is needed to hold 2^512, the initial value of `current_max`.
+const LIMBS: usize = 9;
+
+/// A fixed size unsigned integer, big enough for any number a genome holds.
+///
+/// The limbs are little endian, so `0[0]` holds the least significant bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Wide([u64; LIMBS]);
+
+impl Wide {
+    /// The integer that `bytes` encode, most significant byte first.
+    fn from_bytes_be(bytes: &[u8]) -> Self {
+        let mut limbs = [0u64; LIMBS];
+        for (i, &byte) in bytes.iter().rev().enumerate() {
+            limbs[i / 8] |= (byte as u64) << (8 * (i % 8));
+        }
+        Wide(limbs)
+    }
+
+    /// 2^`exponent`.
+    fn pow2(exponent: usize) -> Self {
+        let mut limbs = [0u64; LIMBS];
+        limbs[exponent / 64] = 1 << (exponent % 64);
+        Wide(limbs)
+    }
+
+    /// Divide this number by `divisor` in place, and return the remainder.
+    fn div_rem(&mut self, divisor: u64) -> u64 {
+        let divisor = divisor as u128;
+        let mut remainder: u128 = 0;
+        for limb in self.0.iter_mut().rev() {
+            let current = (remainder << 64) | *limb as u128;
+            *limb = (current / divisor) as u64;
+            remainder = current % divisor;
+        }
+        remainder as u64
+    }
+
+    /// Is this number smaller than `other`?
+    fn less_than(&self, other: u64) -> bool {
+        self.0[1..].iter().all(|&limb| limb == 0) && self.0[0] < other
+    }
+
+    /// The number of bits of entropy needed to choose one of this many values.
+    fn entropy_bits(&self) -> usize {
+        let mut minus_one = *self;
+        for limb in minus_one.0.iter_mut() {
+            let (value, borrow) = limb.overflowing_sub(1);
+            *limb = value;
+            if !borrow {
+                break;
+            }
+        }
+        if self.0.iter().all(|&limb| limb == 0) {
+            return 0;
+        }
+        match minus_one.0.iter().rposition(|&limb| limb != 0) {
+            Some(i) => i * 64 + 64 - minus_one.0[i].leading_zeros() as usize,
+            None => 0,
+        }
+    }
 }
 
 pub struct Genome {
     /// Used for making choices
-    remaining: BigUint,
+    remaining: Wide,
 
     /// Biggest that `remaining` could be, given past choices.
     ///
     /// When this reaches 0, the Genotype is exhausted.
-    current_max: BigUint,
+    current_max: Wide,
 }
 
 impl Genome {
-    pub fn via_sha512<T>(input: T) -> Self
-    where
-        T: Into<String>,
-    {
-        let input = input.into();
-        let hash = Sha512::digest(input.as_bytes());
+    pub fn via_sha512(input: &[u8]) -> Self {
+        Self::from_digest(&sha512(input))
+    }
+
+    /// Like `via_sha512`, but hashed with HMAC-SHA512 keyed by `key`, so that
+    /// the genome for an input can't be found without the key.
+    pub fn via_hmac_sha512(key: &[u8], input: &[u8]) -> Self {
+        Self::from_digest(&hmac_sha512(key, input))
+    }
+
+    /// A genome made from a hash that has already been computed, such as the
+    /// output of SHA-512.
+    pub fn from_digest(digest: &[u8; 64]) -> Self {
         Self {
-            remaining: BigUint::from_bytes_be(&hash),
-            current_max: MAX_512_BIT.clone(),
+            remaining: Wide::from_bytes_be(digest),
+            current_max: Wide::pow2(512),
         }
     }
 
@@ -40,17 +105,16 @@ impl Genome {
     ///
     /// Returns an error if there isn't enough entropy remaining to fulfill the
     /// request.
-    fn take<T>(&mut self, size: T) -> Result<BigUint, ()>
-    where
-        T: Into<BigUint>,
-    {
-        let size = size.into();
-        if size > self.current_max {
-            return Err(());
-        }
-        let res = (&self.remaining).rem(&size);
-        self.remaining = (&self.remaining).div(&size);
-        self.current_max /= &size;
+    fn take(&mut self, size: u64) -> GenomeResult<u64> {
+        if self.current_max.less_than(size) {
+            return Err(Error::GenomeExhausted {
+                requested_bits: Wide::from_bytes_be(&size.to_be_bytes()).entropy_bits(),
+                remaining_bits: self.current_max.entropy_bits(),
+            });
+        }
+        assert!(size > 0, "can't choose from no values");
+        let res = self.remaining.div_rem(size);
+        self.current_max.div_rem(size);
         Ok(res)
     }
 
@@ -58,11 +122,16 @@ impl Genome {
         T::gen(self)
     }
 
+    /// Like `gen`, customized by `options`.
+    pub fn gen_with<T: GenomeGen>(&mut self, options: &GenerationOptions) -> GenomeResult<T> {
+        T::gen_with(self, options)
+    }
+
     pub fn gen_range<T: GenomeGenRange>(&mut self, low: T, high: T) -> GenomeResult<T> {
         T::gen_range(self, low, high)
     }
 
-    pub fn choose<T: Clone>(&mut self, choices: &Vec<T>) -> GenomeResult<T> {
+    pub fn choose<T: Clone>(&mut self, choices: &[T]) -> GenomeResult<T> {
         Ok(choices[self.gen_range(0, choices.len())?].clone())
     }
 
@@ -79,11 +148,38 @@ impl Genome {
     }
 }
 
-pub type GenomeResult<T> = Result<T, ()>;
+/// SHA-512, as a fixed size digest.
+fn sha512(message: &[u8]) -> [u8; 64] {
+    let mut digest = [0; 64];
+    digest.copy_from_slice(&Sha512::digest(message));
+    digest
+}
+
+/// HMAC-SHA512, as described in RFC 2104.
+fn hmac_sha512(key: &[u8], message: &[u8]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_varkey(key).expect("HMAC accepts keys of any length");
+    mac.input(message);
+    let mut digest = [0; 64];
+    digest.copy_from_slice(&mac.result().code());
+    digest
+}
+
+pub type GenomeResult<T> = Result<T, Error>;
 
 /// Implement this trait to generate a value of type from a Genome
+///
+/// Implement at least one of `gen` and `gen_with`. Values that don't depend
+/// on generation options implement `gen`, and icons implement `gen_with`.
 pub trait GenomeGen: Sized {
-    fn gen(genome: &mut Genome) -> GenomeResult<Self>;
+    fn gen(genome: &mut Genome) -> GenomeResult<Self> {
+        genome.gen_with(&GenerationOptions::default())
+    }
+
+    /// Generate a value like `gen`, customized by `options`.
+    fn gen_with(genome: &mut Genome, options: &GenerationOptions) -> GenomeResult<Self> {
+        let _ = options;
+        genome.gen()
+    }
 }
 
 /// Implement this trait to generate a value of type in a range from a Genome
@@ -92,22 +188,154 @@ pub trait GenomeGenRange: Sized {
 }
 
 macro_rules! genome_gens_int {
-    ($t:ty, $to_prim:ident) => {
+    ($t:ty) => {
         impl GenomeGen for $t {
             fn gen(genome: &mut Genome) -> GenomeResult<$t> {
-                genome.take(Self::max_value())?.$to_prim().ok_or(())
+                // The choice is smaller than the type's maximum, so it fits.
+                Ok(genome.take(<$t>::max_value() as u64)? as $t)
             }
         }
 
         impl GenomeGenRange for $t {
             fn gen_range(genome: &mut Genome, low: $t, high: $t) -> GenomeResult<$t> {
-                Ok(genome.take(high - low)?.$to_prim().ok_or(())? + low)
+                // The choice is smaller than the range's size, so it fits.
+                let choice = genome.take((high - low) as u64)? as $t;
+                Ok(choice + low)
             }
         }
     };
 }
 
-genome_gens_int!(usize, to_usize);
-genome_gens_int!(u8, to_u8);
-genome_gens_int!(u16, to_u16);
-genome_gens_int!(u64, to_u64);
+genome_gens_int!(usize);
+genome_gens_int!(u8);
+genome_gens_int!(u16);
+genome_gens_int!(u64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num::{bigint::ToBigUint, pow::pow, BigUint, One, ToPrimitive, Zero};
+    use proptest::collection::vec;
+
+    /// The original genome, which used arbitrary precision integers. The
+    /// fixed size genome must make exactly the same choices.
+    struct BigGenome {
+        remaining: BigUint,
+        current_max: BigUint,
+    }
+
+    impl BigGenome {
+        fn via_sha512(input: &str) -> Self {
+            BigGenome {
+                remaining: BigUint::from_bytes_be(&Sha512::digest(input.as_bytes())),
+                current_max: pow(2u32.to_biguint().unwrap(), 512),
+            }
+        }
+
+        fn take(&mut self, size: u64) -> Result<u64, (usize, usize)> {
+            let size = BigUint::from(size);
+            if size > self.current_max {
+                return Err((big_entropy_bits(&size), big_entropy_bits(&self.current_max)));
+            }
+            let res = &self.remaining % &size;
+            self.remaining = &self.remaining / &size;
+            self.current_max = &self.current_max / &size;
+            Ok(res.to_u64().unwrap())
+        }
+    }
+
+    fn big_entropy_bits(size: &BigUint) -> usize {
+        if size.is_zero() {
+            0
+        } else {
+            (size - BigUint::one()).bits()
+        }
+    }
+
+    #[test]
+    fn test_exhaustion() {
+        let mut genome = Genome::via_sha512(b"exhaust");
+        for _ in 0..8 {
+            genome.gen::<u64>().unwrap();
+        }
+        match genome.gen::<u64>() {
+            Err(Error::GenomeExhausted {
+                requested_bits,
+                remaining_bits,
+            }) => {
+                assert_eq!(requested_bits, 64);
+                assert_eq!(remaining_bits, 0);
+            }
+            other => panic!("expected the genome to be exhausted, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_hmac() {
+        // Test case 2 from RFC 4231.
+        let genome = Genome::via_hmac_sha512(b"Jefe", b"what do ya want for nothing?");
+        let expected = [
+            0x164b7a7bfcf819e2,
+            0xe395fbe73b56e0a3,
+            0x87bd64222e831fd6,
+            0x10270cd7ea250554,
+            0x9758bf75c05a994a,
+            0x6d034f65f8f0e6fd,
+            0xcaeab1a34d4a6b4b,
+            0x636e070a38bce737,
+        ];
+        for (i, &limb) in expected.iter().rev().enumerate() {
+            assert_eq!(genome.remaining.0[i], limb);
+        }
+
+        // Test case 6, which has a key longer than a block.
+        let mac = hmac_sha512(
+            &[0xaa; 131],
+            b"Test Using Larger Than Block-Size Key - Hash Key First",
+        );
+        assert_eq!(&mac[..8], &[0x80, 0xb2, 0x42, 0x63, 0xc7, 0xc1, 0xa3, 0xeb]);
+        assert_eq!(
+            &mac[56..],
+            &[0x8b, 0x91, 0x5a, 0x98, 0x5d, 0x78, 0x65, 0x98]
+        );
+
+        assert_ne!(
+            Genome::via_hmac_sha512(b"Jefe", b"one").remaining,
+            Genome::via_sha512(b"one").remaining
+        );
+    }
+
+    #[test]
+    fn test_wide() {
+        let mut n = Wide::pow2(512);
+        assert_eq!(n.entropy_bits(), 512);
+        assert_eq!(n.div_rem(3), 1);
+        assert!(!n.less_than(u64::MAX));
+        assert_eq!(Wide::pow2(0).entropy_bits(), 0);
+        assert_eq!(Wide::from_bytes_be(&[0, 0, 5]).entropy_bits(), 3);
+        assert!(Wide::from_bytes_be(&[0x12, 0x34]).less_than(0x1235));
+        assert!(!Wide::from_bytes_be(&[0x12, 0x34]).less_than(0x1234));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_matches_big_genome(
+            ref seed in ".*",
+            ref sizes in vec(prop_oneof![1u64..300, 1u64..], 0..40)
+        ) {
+            let mut genome = Genome::via_sha512(seed.as_bytes());
+            let mut big = BigGenome::via_sha512(seed);
+            for &size in sizes {
+                let expected = big.take(size);
+                match genome.take(size) {
+                    Ok(choice) => assert_eq!(Ok(choice), expected),
+                    Err(Error::GenomeExhausted { requested_bits, remaining_bits }) => {
+                        assert_eq!(Err((requested_bits, remaining_bits)), expected);
+                        break;
+                    }
+                    Err(err) => panic!("unexpected error {:?}", err),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,333 @@
+extern crate tera;
+
+use super::raster;
+use super::render::{RenderOptions, ViewBox};
+use super::{
+    genome::{Genome, GenomeGen, GenomeResult},
+    templ, Color, Error, GenerationOptions,
+};
+use std::default::Default;
+use tiny_skia::{PathBuilder, Pixmap, Rect};
+
+/// A description of a grid icon: a square of cells that are either filled
+/// or empty, mirrored left to right, like the default avatars on GitHub.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridIconData {
+    /// The number of cells across the grid, and down it.
+    pub size: u8,
+    /// The color of the filled cells.
+    pub foreground: Color,
+    /// The color behind the cells.
+    pub background: Color,
+    /// Whether each cell is filled, row by row from the top.
+    pub cells: Vec<Vec<bool>>,
+}
+
+impl GridIconData {
+    /// Generate a GridIconData by hashing an input and choosing unpredictable
+    /// values for all parameters.
+    pub fn from_input<T: Into<String>>(input: T) -> Result<Self, Error> {
+        Self::from_input_with_options(input, &GenerationOptions::default())
+    }
+
+    /// Generate a GridIconData like `from_input`, customized by `options`.
+    ///
+    /// Grids have no emoji, so the `emoji` and `text` options are ignored.
+    pub fn from_input_with_options<T: Into<String>>(
+        input: T,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        Self::from_bytes_with_options(input.into().as_bytes(), options)
+    }
+
+    /// Generate a GridIconData by hashing arbitrary bytes, such as a UUID or a
+    /// public key. A string's UTF-8 bytes give the same icon as `from_input`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_options(bytes, &GenerationOptions::default())
+    }
+
+    /// Generate a GridIconData like `from_bytes`, customized by `options`.
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.genome(bytes);
+        genome.gen_with(options)
+    }
+
+    /// Generate a GridIconData from a SHA-512 digest that has already been
+    /// computed, such as a content hash. The digest of some bytes gives the
+    /// same icon as `from_bytes`.
+    pub fn from_digest(digest: &[u8; 64]) -> Result<Self, Error> {
+        Self::from_digest_with_options(digest, &GenerationOptions::default())
+    }
+
+    /// Generate a GridIconData like `from_digest`, customized by `options`.
+    ///
+    /// Returns an error if `options` has a key, since the digest is used as
+    /// is. Compute it with HMAC-SHA512 to key it.
+    pub fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.digest_genome(digest)?;
+        genome.gen_with(options)
+    }
+
+    /// Render as an SVG.
+    pub fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        let mut context = tera::Context::new();
+        context.add("icon", &self);
+        context.add("svg", &options.svg_attributes(self, self.view_box())?);
+        context.add("side", &self.view_box().width);
+        context.add("filled", &self.filled_cells()?);
+        Ok(templ::render("grid.svg.tera", &context)?)
+    }
+
+    /// Render as a `size`x`size` PNG, with the same geometry as the SVG.
+    pub fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_png(&self.to_pixmap(size)?)
+    }
+
+    /// Render as a `size`x`size` lossless WebP, drawn the same way as
+    /// `to_png`.
+    pub fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_webp(&self.to_pixmap(size)?)
+    }
+
+    fn to_pixmap(&self, size: u32) -> Result<Pixmap, Error> {
+        let view_box = self.view_box();
+        let filled = self.filled_cells()?;
+        raster::render(size, view_box, None, |pixmap, transform| {
+            let background = (0.0, 0.0, view_box.width, view_box.height);
+            raster::fill_rect(pixmap, background, self.background, transform, None);
+
+            // The cells are one path, so that there are no seams between
+            // neighbouring cells.
+            let mut pb = PathBuilder::new();
+            for &(x, y) in &filled {
+                if let Some(rect) = Rect::from_xywh(x, y, 1.0, 1.0) {
+                    pb.push_rect(rect);
+                }
+            }
+            if let Some(path) = pb.finish() {
+                raster::fill_path(pixmap, &path, self.foreground, transform, None);
+            }
+            Ok(())
+        })
+    }
+
+    /// The area the grid is drawn in, with a margin of half a cell around
+    /// it. Each cell is one unit square.
+    fn view_box(&self) -> ViewBox {
+        let side = self.size as f32 + 1.0;
+        ViewBox {
+            x: 0.0,
+            y: 0.0,
+            width: side,
+            height: side,
+        }
+    }
+
+    /// The top left corner of each filled cell.
+    ///
+    /// Returns an error if `cells` isn't `size` rows of `size` cells.
+    fn filled_cells(&self) -> Result<Vec<(f32, f32)>, Error> {
+        let size = self.size as usize;
+        if size == 0 || self.cells.len() != size || self.cells.iter().any(|r| r.len() != size) {
+            return Err(Error::InvalidData(format!(
+                "a grid of size {} needs {} rows of {} cells",
+                size, size, size
+            )));
+        }
+        let mut filled = vec![];
+        for (y, row) in self.cells.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                if cell {
+                    filled.push((x as f32 + 0.5, y as f32 + 0.5));
+                }
+            }
+        }
+        Ok(filled)
+    }
+}
+
+impl GenomeGen for GridIconData {
+    fn gen_with(genome: &mut Genome, options: &GenerationOptions) -> GenomeResult<Self> {
+        let foreground = genome.choose(&options.palette.pairable(None, options.contrast)?)?;
+        let backgrounds = options
+            .palette
+            .contrasting_with(&foreground, options.contrast)?;
+        let background = genome.choose(&backgrounds)?;
+
+        let size: u8 = genome.choose_weighted(&vec![(5, 2), (6, 1), (7, 1)])?;
+
+        // Only the left half, and the middle column of odd sizes, are chosen.
+        // The right half mirrors them.
+        let width = size as usize;
+        let mut cells = vec![];
+        for _ in 0..width {
+            let mut row = vec![false; width];
+            for x in 0..width.div_ceil(2) {
+                let filled = genome.choose(&[false, true])?;
+                row[x] = filled;
+                row[width - 1 - x] = filled;
+            }
+            cells.push(row);
+        }
+
+        Ok(GridIconData {
+            size,
+            foreground,
+            background,
+            cells,
+        })
+    }
+}
+
+impl Default for GridIconData {
+    fn default() -> Self {
+        GridIconData {
+            size: 5,
+            foreground: Color::black(),
+            background: Color::white(),
+            cells: vec![vec![false; 5]; 5],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use {ContrastMode, Palette};
+
+    /// Parse rows like `".##.#"`, where `#` is a filled cell.
+    fn cells(rows: &[&str]) -> Vec<Vec<bool>> {
+        rows.iter()
+            .map(|row| row.chars().map(|c| c == '#').collect())
+            .collect()
+    }
+
+    /// Test that certain seeds always generate the same icon
+    /// data. This is to make sure that icons don't change overtime,
+    /// since they are supposed to always be the same for a particular
+    /// hash.
+    #[test]
+    fn test_consistent_icons() {
+        let expected = GridIconData {
+            size: 5,
+            foreground: Color {
+                r: 125,
+                g: 0,
+                b: 79,
+            },
+            background: Color {
+                r: 0,
+                g: 254,
+                b: 255,
+            },
+            cells: cells(&[".###.", ".###.", ".###.", "..#..", ".#.#."]),
+        };
+        let actual = GridIconData::from_input("one").unwrap();
+        assert_eq!(expected, actual);
+
+        // ----
+
+        let expected = GridIconData {
+            size: 5,
+            foreground: Color {
+                r: 148,
+                g: 0,
+                b: 255,
+            },
+            background: Color {
+                r: 48,
+                g: 230,
+                b: 11,
+            },
+            cells: cells(&[".#.#.", "..#..", ".###.", "#.#.#", "#.#.#"]),
+        };
+        let actual = GridIconData::from_input("two").unwrap();
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_mirrored() {
+        let mut sizes = vec![];
+        for i in 0..100 {
+            let icon = GridIconData::from_input(i.to_string()).unwrap();
+            assert_eq!(icon.cells.len(), icon.size as usize);
+            for row in &icon.cells {
+                let mirrored: Vec<bool> = row.iter().rev().cloned().collect();
+                assert_eq!(row, &mirrored);
+            }
+            sizes.push(icon.size);
+        }
+        for size in 5..8 {
+            assert!(sizes.contains(&size), "no grid was {} cells wide", size);
+        }
+    }
+
+    #[test]
+    fn test_wcag_contrast() {
+        let options = GenerationOptions {
+            palette: Palette::from_json(r##"{"navy": "#001f3f", "gold": "#ffd700"}"##).unwrap(),
+            contrast: ContrastMode::WCAG_AA,
+            ..GenerationOptions::default()
+        };
+        for i in 0..20 {
+            let icon = GridIconData::from_input_with_options(i.to_string(), &options).unwrap();
+            assert!(icon.foreground.contrast_ratio(&icon.background) >= 4.5);
+        }
+    }
+
+    #[test]
+    fn test_rendering() {
+        let icon = GridIconData {
+            size: 5,
+            foreground: Color { r: 0, g: 0, b: 255 },
+            background: Color::white(),
+            cells: cells(&["#...#", ".....", ".....", ".....", "....."]),
+        };
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 6 6""#));
+        assert!(svg.contains(r#"d="M 0.5 0.5 h 1 v 1 h -1 z M 4.5 0.5 h 1 v 1 h -1 z""#));
+
+        // At 60 pixels, each cell is 10 pixels, after a margin of 5.
+        let png = icon.to_png(60).unwrap();
+        let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        for &(x, y, expected) in &[
+            (2, 2, Color::white()),
+            (10, 10, icon.foreground),
+            (30, 10, Color::white()),
+            (50, 10, icon.foreground),
+        ] {
+            let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+            assert_eq!(
+                (pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()),
+                (expected.r, expected.g, expected.b, 255),
+                "pixel at {}, {}",
+                x,
+                y
+            );
+        }
+        assert!(icon.to_webp(60).is_ok());
+
+        let ragged = GridIconData {
+            cells: cells(&["#...#"]),
+            ..icon
+        };
+        match ragged.to_svg(&RenderOptions::default()) {
+            Err(Error::InvalidData(_)) => (),
+            other => panic!("expected invalid data, got {:?}", other),
+        }
+        assert!(ragged.to_png(60).is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_does_not_exhaust_genome(ref s in ".*") {
+            assert!(GridIconData::from_input(s.clone()).is_ok());
+        }
+    }
+}
@@ -0,0 +1,384 @@
+//! A common interface to all families of identicons.
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json;
+use std::fmt::Debug;
+
+use super::{
+    BlockiesIconData, Error, GenerationOptions, GridIconData, RenderOptions, ShapeIconData,
+    ShieldIconData,
+};
+
+/// An identicon from one of the families this crate can generate.
+///
+/// This trait is object safe, so icons of different families can be handled
+/// together as `Box<dyn Identicon>`.
+///
+///     # use identicons::{Identicon, RenderOptions, ShapeIconData, ShieldIconData};
+///     let icons: Vec<Box<dyn Identicon>> = vec![
+///         Box::new(ShieldIconData::from_seed("example").unwrap()),
+///         Box::new(ShapeIconData::from_seed("example").unwrap()),
+///     ];
+///     for icon in icons {
+///         let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+///         assert!(svg.starts_with("<?xml"));
+///     }
+///
+pub trait Identicon: Debug {
+    /// Generate an icon by hashing a seed and choosing unpredictable values
+    /// for all parameters.
+    fn from_seed(seed: &str) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::from_seed_with_options(seed, &GenerationOptions::default())
+    }
+
+    /// Generate an icon from a seed like `from_seed`, customized by `options`.
+    fn from_seed_with_options(seed: &str, options: &GenerationOptions) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::from_bytes_with_options(seed.as_bytes(), options)
+    }
+
+    /// Generate an icon by hashing arbitrary bytes. A seed's UTF-8 bytes give
+    /// the same icon as `from_seed`.
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::from_bytes_with_options(bytes, &GenerationOptions::default())
+    }
+
+    /// Generate an icon from bytes like `from_bytes`, customized by `options`.
+    fn from_bytes_with_options(bytes: &[u8], options: &GenerationOptions) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// Generate an icon from a precomputed SHA-512 digest. The digest of some
+    /// bytes gives the same icon as `from_bytes`.
+    fn from_digest(digest: &[u8; 64]) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        Self::from_digest_with_options(digest, &GenerationOptions::default())
+    }
+
+    /// Generate an icon from a digest like `from_digest`, customized by
+    /// `options`, which must not have a key.
+    fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// Generate an icon from bytes like `from_bytes_with_options`, with a
+    /// particular version of the family's algorithm.
+    ///
+    /// Families with only one version return an error for any other version.
+    fn from_bytes_with_version(
+        bytes: &[u8],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        check_version(Self::from_bytes_with_options(bytes, options)?, version)
+    }
+
+    /// Generate an icon from a digest like `from_digest_with_options`, with a
+    /// particular version of the family's algorithm.
+    fn from_digest_with_version(
+        digest: &[u8; 64],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error>
+    where
+        Self: Sized,
+    {
+        check_version(Self::from_digest_with_options(digest, options)?, version)
+    }
+
+    /// Whether icons of this family can be generated with a seed key.
+    ///
+    /// Families that follow a reference implementation can't, so that their
+    /// icons look the same as everywhere else. They return an error for
+    /// options with a key, so drop the key before generating them.
+    fn accepts_key() -> bool
+    where
+        Self: Sized,
+    {
+        true
+    }
+
+    /// Read an icon back from the JSON produced by `to_json`.
+    fn from_json(json: &str) -> Result<Self, Error>
+    where
+        Self: Sized;
+
+    /// The name of the family of icons this belongs to, such as `"shield"`.
+    fn family(&self) -> &'static str;
+
+    /// The version of the family's generation algorithm.
+    fn version(&self) -> u32;
+
+    /// Render as an SVG.
+    fn to_svg(&self, options: &RenderOptions) -> Result<String, Error>;
+
+    /// Render as a `size`x`size` PNG.
+    fn to_png(&self, size: u32) -> Result<Vec<u8>, Error>;
+
+    /// Render as a `size`x`size` lossless WebP.
+    fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error>;
+
+    /// Serialize the parameters of this icon as JSON.
+    fn to_json(&self) -> Result<String, Error>;
+}
+
+fn check_version<T: Identicon>(icon: T, version: u32) -> Result<T, Error> {
+    if icon.version() == version {
+        Ok(icon)
+    } else {
+        Err(Error::InvalidData(format!(
+            "{} icons have no version {}",
+            icon.family(),
+            version
+        )))
+    }
+}
+
+fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, Error> {
+    Ok(serde_json::from_str(json)?)
+}
+
+fn to_json<T: Serialize>(icon: &T) -> Result<String, Error> {
+    Ok(serde_json::to_string(icon)?)
+}
+
+impl Identicon for ShieldIconData {
+    fn from_bytes_with_options(bytes: &[u8], options: &GenerationOptions) -> Result<Self, Error> {
+        ShieldIconData::from_bytes_with_options(bytes, options)
+    }
+
+    fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        ShieldIconData::from_digest_with_options(digest, options)
+    }
+
+    fn from_bytes_with_version(
+        bytes: &[u8],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        ShieldIconData::from_bytes_with_version(bytes, version, options)
+    }
+
+    fn from_digest_with_version(
+        digest: &[u8; 64],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        ShieldIconData::from_digest_with_version(digest, version, options)
+    }
+
+    fn from_json(json: &str) -> Result<Self, Error> {
+        from_json(json)
+    }
+
+    fn family(&self) -> &'static str {
+        "shield"
+    }
+
+    fn version(&self) -> u32 {
+        ShieldIconData::version(self)
+    }
+
+    fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        ShieldIconData::to_svg(self, options)
+    }
+
+    fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        ShieldIconData::to_png(self, size)
+    }
+
+    fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        ShieldIconData::to_webp(self, size)
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        to_json(self)
+    }
+}
+
+impl Identicon for ShapeIconData {
+    fn from_bytes_with_options(bytes: &[u8], options: &GenerationOptions) -> Result<Self, Error> {
+        ShapeIconData::from_bytes_with_options(bytes, options)
+    }
+
+    fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        ShapeIconData::from_digest_with_options(digest, options)
+    }
+
+    fn from_json(json: &str) -> Result<Self, Error> {
+        from_json(json)
+    }
+
+    fn family(&self) -> &'static str {
+        "shape"
+    }
+
+    fn version(&self) -> u32 {
+        0
+    }
+
+    fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        ShapeIconData::to_svg(self, options)
+    }
+
+    fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        ShapeIconData::to_png(self, size)
+    }
+
+    fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        ShapeIconData::to_webp(self, size)
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        to_json(self)
+    }
+}
+
+impl Identicon for GridIconData {
+    fn from_bytes_with_options(bytes: &[u8], options: &GenerationOptions) -> Result<Self, Error> {
+        GridIconData::from_bytes_with_options(bytes, options)
+    }
+
+    fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        GridIconData::from_digest_with_options(digest, options)
+    }
+
+    fn from_json(json: &str) -> Result<Self, Error> {
+        from_json(json)
+    }
+
+    fn family(&self) -> &'static str {
+        "grid"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        GridIconData::to_svg(self, options)
+    }
+
+    fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        GridIconData::to_png(self, size)
+    }
+
+    fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        GridIconData::to_webp(self, size)
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        to_json(self)
+    }
+}
+
+impl Identicon for BlockiesIconData {
+    fn from_bytes_with_options(bytes: &[u8], options: &GenerationOptions) -> Result<Self, Error> {
+        BlockiesIconData::from_bytes_with_options(bytes, options)
+    }
+
+    fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        BlockiesIconData::from_digest_with_options(digest, options)
+    }
+
+    fn accepts_key() -> bool {
+        false
+    }
+
+    fn from_json(json: &str) -> Result<Self, Error> {
+        from_json(json)
+    }
+
+    fn family(&self) -> &'static str {
+        "blockies"
+    }
+
+    fn version(&self) -> u32 {
+        1
+    }
+
+    fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
+        BlockiesIconData::to_svg(self, options)
+    }
+
+    fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        BlockiesIconData::to_png(self, size)
+    }
+
+    fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        BlockiesIconData::to_webp(self, size)
+    }
+
+    fn to_json(&self) -> Result<String, Error> {
+        to_json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: Identicon + PartialEq>(icon: T) {
+        let json = icon.to_json().unwrap();
+        assert_eq!(T::from_json(&json).unwrap(), icon);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        round_trip(ShieldIconData::from_seed("one").unwrap());
+        let options = GenerationOptions::default();
+        round_trip(ShieldIconData::from_bytes_with_version(b"one", 2, &options).unwrap());
+        round_trip(ShapeIconData::from_seed("one").unwrap());
+        round_trip(GridIconData::from_seed("one").unwrap());
+        round_trip(
+            BlockiesIconData::from_seed("0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359").unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_trait_objects() {
+        let icons: Vec<Box<dyn Identicon>> = vec![
+            Box::new(ShieldIconData::from_seed("one").unwrap()),
+            Box::new(ShapeIconData::from_seed("one").unwrap()),
+        ];
+        let names: Vec<_> = icons.iter().map(|i| (i.family(), i.version())).collect();
+        assert_eq!(names, vec![("shield", 1), ("shape", 0)]);
+
+        let options = RenderOptions::default();
+        assert_eq!(
+            icons[0].to_svg(&options).unwrap(),
+            ShieldIconData::from_input("one")
+                .unwrap()
+                .to_svg(&options)
+                .unwrap()
+        );
+    }
+}
@@ -11,8 +11,10 @@
 
 #![deny(missing_docs)]
 
-extern crate num;
+extern crate hmac;
+extern crate image_webp;
 extern crate rand;
+extern crate resvg;
 extern crate tera;
 #[macro_use]
 extern crate lazy_static;
@@ -21,19 +23,56 @@ extern crate serde;
 extern crate serde_derive;
 extern crate serde_json;
 extern crate sha2;
+extern crate tiny_skia;
+extern crate toml;
+extern crate ttf_parser;
+extern crate unicode_segmentation;
 
+#[cfg(test)]
+extern crate num;
 #[cfg(test)]
 #[macro_use]
 extern crate proptest;
 
+mod blockies;
 mod data;
+mod emoji;
+mod emoji_set;
+mod error;
+mod generation;
 mod genome;
+mod grids;
+mod identicon;
+mod palette;
+mod raster;
+mod registry;
+mod render;
 mod shapes;
 mod shields;
+mod silhouettes;
 mod templ;
+mod text;
 
+pub use self::blockies::{BlockiesColor, BlockiesIconData};
+pub use self::emoji_set::{EmojiCategory, EmojiSet};
+pub use self::error::Error;
+pub use self::generation::{ContrastMode, GenerationOptions, SeedKey};
+pub use self::grids::GridIconData;
+pub use self::identicon::Identicon;
+pub use self::palette::Palette;
+pub use self::raster::RasterError;
+pub use self::registry::{Generator, Registry};
+pub use self::render::{Aspect, EmojiStyle, RenderOptions};
 pub use self::shapes::{ShapeIconData, ShapeType};
-pub use self::shields::{ShieldIconData, ShieldIconTreatment};
+pub use self::shields::{ShieldCharge, ShieldChargeShape, ShieldIconData, ShieldIconTreatment};
+pub use self::silhouettes::ShieldSilhouette;
+
+/// The version of this crate.
+///
+/// An icon's JSON never changes within a version of its family, but how it
+/// is drawn can change between releases, so caches of rendered icons should
+/// depend on this as well.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// An RGB color.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -61,6 +100,27 @@ impl Color {
         }
     }
 
+    /// Parse a color written as `#rrggbb`.
+    ///
+    ///     # use identicons::Color;
+    ///     assert_eq!(Color::from_hex("#0a84ff"), Some(Color { r: 10, g: 132, b: 255 }));
+    ///     assert_eq!(Color::from_hex("0a84ff"), None);
+    ///
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        if hex.len() != 7
+            || !hex.starts_with('#')
+            || !hex[1..].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return None;
+        }
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+        Some(Self {
+            r: component(1),
+            g: component(3),
+            b: component(5),
+        })
+    }
+
     /// Format this color as a CSS color.
     ///
     ///     # use identicons::Color;
@@ -77,7 +137,41 @@ impl Color {
     }
 
     /// Does this color contrast well with that other color?
+    ///
+    /// This is the heuristic used by `ContrastMode::Legacy`. It compares
+    /// `luminance`, which doesn't account for gamma, so `contrast_ratio` is a
+    /// better measure of how readable two colors are together.
     pub fn contrasts_well(&self, other: &Self) -> bool {
         (self.luminance() - other.luminance()).abs() > 75.0
     }
+
+    /// Get this color's relative luminance, as defined by [WCAG 2][wcag],
+    /// from 0 for black to 1 for white.
+    ///
+    /// [wcag]: https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+    pub fn relative_luminance(&self) -> f32 {
+        let linear = |c: u8| {
+            let c = c as f32 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b)
+    }
+
+    /// Get the [WCAG 2 contrast ratio][wcag] between this color and another,
+    /// from 1 for identical colors to 21 for black and white.
+    ///
+    ///     # use identicons::Color;
+    ///     assert_eq!(Color::black().contrast_ratio(&Color::white()).round(), 21.0);
+    ///     let grey = Color { r: 0x76, g: 0x76, b: 0x76 };
+    ///     assert!(grey.contrast_ratio(&Color::white()) >= 4.5);
+    ///
+    /// [wcag]: https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+    pub fn contrast_ratio(&self, other: &Self) -> f32 {
+        let (a, b) = (self.relative_luminance(), other.relative_luminance());
+        (a.max(b) + 0.05) / (a.min(b) + 0.05)
+    }
 }
@@ -0,0 +1,258 @@
+//! Named sets of colors that icons are generated from.
+
+use serde_json;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use toml;
+
+use super::{data, Color, ContrastMode, Error};
+
+/// The Photon palette, built once, since every default set of generation
+/// options starts from it.
+static PHOTON: OnceLock<Palette> = OnceLock::new();
+
+/// A named set of colors for icons to be generated from.
+///
+/// Palettes can be loaded from JSON or TOML that maps color names to hex
+/// colors:
+///
+///     # use identicons::{Color, Palette};
+///     let palette = Palette::from_json(r##"{
+///         "ink": "#202340",
+///         "paper": "#f9f9fa",
+///         "accent": "#ff9400"
+///     }"##).unwrap();
+///     assert_eq!(palette.get("accent"), Some(Color { r: 0xff, g: 0x94, b: 0x00 }));
+///
+/// Icons only depend on the colors of a palette and the order of their
+/// names, so renaming colors will change the icons generated from it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    colors: BTreeMap<String, Color>,
+}
+
+impl Palette {
+    /// Create a palette from pairs of names and colors.
+    ///
+    /// Returns an error if there are no colors.
+    pub fn new<I, S>(colors: I) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = (S, Color)>,
+        S: Into<String>,
+    {
+        let colors: BTreeMap<String, Color> = colors
+            .into_iter()
+            .map(|(name, color)| (name.into(), color))
+            .collect();
+        if colors.is_empty() {
+            return Err(Error::Palette(
+                "a palette needs at least one color".to_string(),
+            ));
+        }
+        Ok(Palette { colors })
+    }
+
+    /// The colors of [Firefox Photon][photon], which icons are generated
+    /// from by default.
+    ///
+    /// [photon]: https://design.firefox.com/photon/visuals/color.html
+    pub fn photon() -> Self {
+        PHOTON
+            .get_or_init(|| {
+                Palette::new(data::COLOR_MAP.iter().map(|(name, color)| (*name, *color)))
+                    .expect("the Photon palette has colors")
+            })
+            .clone()
+    }
+
+    /// Load a palette from a JSON object that maps names to hex colors.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let colors: BTreeMap<String, String> =
+            serde_json::from_str(json).map_err(|err| Error::Palette(err.to_string()))?;
+        Palette::from_hex_colors(colors)
+    }
+
+    /// Load a palette from a TOML table that maps names to hex colors.
+    pub fn from_toml(source: &str) -> Result<Self, Error> {
+        let colors: BTreeMap<String, String> =
+            toml::from_str(source).map_err(|err| Error::Palette(err.to_string()))?;
+        Palette::from_hex_colors(colors)
+    }
+
+    fn from_hex_colors(colors: BTreeMap<String, String>) -> Result<Self, Error> {
+        let mut parsed = Vec::with_capacity(colors.len());
+        for (name, hex) in colors {
+            let color = Color::from_hex(&hex).ok_or_else(|| {
+                Error::Palette(format!("{:?} is not a valid color for {:?}", hex, name))
+            })?;
+            parsed.push((name, color));
+        }
+        Palette::new(parsed)
+    }
+
+    /// Get a color by name.
+    pub fn get(&self, name: &str) -> Option<Color> {
+        self.colors.get(name).cloned()
+    }
+
+    /// The colors in this palette, ordered by their names.
+    pub fn colors(&self) -> Vec<Color> {
+        self.colors.values().cloned().collect()
+    }
+
+    /// The colors in this palette that contrast with `other`.
+    ///
+    /// Returns an error if there aren't any, since there would be nothing for
+    /// the generator to choose from.
+    pub(crate) fn contrasting_with(
+        &self,
+        other: &Color,
+        contrast: ContrastMode,
+    ) -> Result<Vec<Color>, Error> {
+        let colors: Vec<Color> = self
+            .colors
+            .values()
+            .filter(|c| contrast.contrasts(other, c))
+            .cloned()
+            .collect();
+        if colors.is_empty() {
+            return Err(Error::Palette(format!(
+                "no colors contrast with {}",
+                other.css_color()
+            )));
+        }
+        Ok(colors)
+    }
+
+    /// The colors in this palette that contrast with `other`, and with at
+    /// least one color in this palette, so that they can be paired with
+    /// another color later.
+    ///
+    /// If `other` is `None`, this is every color that can be paired.
+    pub(crate) fn pairable(
+        &self,
+        other: Option<&Color>,
+        contrast: ContrastMode,
+    ) -> Result<Vec<Color>, Error> {
+        let colors: Vec<Color> = self
+            .colors
+            .values()
+            .filter(|c| match other {
+                Some(other) => contrast.contrasts(other, c),
+                None => true,
+            })
+            .filter(|c| self.colors.values().any(|p| contrast.contrasts(c, p)))
+            .cloned()
+            .collect();
+        if colors.is_empty() {
+            return Err(Error::Palette(match other {
+                Some(other) => format!(
+                    "no colors contrast with both {} and another color",
+                    other.css_color()
+                ),
+                None => "no two colors contrast with each other".to_string(),
+            }));
+        }
+        Ok(colors)
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::photon()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_photon_order() {
+        let mut names: Vec<&&str> = data::COLOR_MAP.keys().collect();
+        names.sort();
+        let expected: Vec<Color> = names.into_iter().map(|n| data::COLOR_MAP[n]).collect();
+        assert_eq!(Palette::photon().colors(), expected);
+    }
+
+    #[test]
+    fn test_from_json_and_toml() {
+        let json = Palette::from_json(r##"{"dark": "#202340", "light": "#F9F9FA"}"##).unwrap();
+        let toml = Palette::from_toml("dark = \"#202340\"\nlight = \"#f9f9fa\"\n").unwrap();
+        assert_eq!(json, toml);
+        assert_eq!(
+            json.colors(),
+            vec![
+                Color {
+                    r: 0x20,
+                    g: 0x23,
+                    b: 0x40,
+                },
+                Color {
+                    r: 0xf9,
+                    g: 0xf9,
+                    b: 0xfa,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_palettes() {
+        assert!(Palette::from_json("{}").is_err());
+        assert!(Palette::from_json("[]").is_err());
+        assert!(Palette::from_json(r#"{"dark": "202340"}"#).is_err());
+        assert!(Palette::from_toml("dark = \"#2023\"").is_err());
+        assert!(Palette::from_toml("dark = 7").is_err());
+    }
+
+    #[test]
+    fn test_contrasting_with() {
+        let palette = Palette::from_json(r##"{"black": "#000000", "white": "#ffffff"}"##).unwrap();
+        assert_eq!(
+            palette
+                .contrasting_with(&Color::black(), ContrastMode::Legacy)
+                .unwrap(),
+            vec![Color::white()]
+        );
+
+        let greys = Palette::from_json(r##"{"a": "#dddddd", "b": "#eeeeee"}"##).unwrap();
+        assert!(greys
+            .contrasting_with(&Color::white(), ContrastMode::Legacy)
+            .is_err());
+    }
+
+    #[test]
+    fn test_pairable() {
+        let photon = Palette::photon();
+        assert_eq!(
+            photon.pairable(None, ContrastMode::Legacy).unwrap(),
+            photon.colors()
+        );
+
+        for &contrast in &[
+            ContrastMode::WCAG_AA_LARGE,
+            ContrastMode::WCAG_AA,
+            ContrastMode::WCAG_AAA,
+        ] {
+            let white = Color::white();
+            for color in photon.pairable(Some(&white), contrast).unwrap() {
+                assert!(contrast.contrasts(&white, &color));
+                assert!(photon.contrasting_with(&color, contrast).is_ok());
+            }
+        }
+
+        // green70 doesn't reach 7:1 with black or white.
+        let palette = Palette::from_json(
+            r##"{"black": "#000000", "green70": "#058b00", "white": "#ffffff"}"##,
+        )
+        .unwrap();
+        assert_eq!(
+            palette.pairable(None, ContrastMode::WCAG_AAA).unwrap(),
+            vec![Color::black(), Color::white()]
+        );
+        assert!(palette
+            .pairable(Some(&Color::white()), ContrastMode::MinimumRatio(21.5))
+            .is_err());
+    }
+}
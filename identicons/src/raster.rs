@@ -0,0 +1,350 @@
+//! Rasterizing icons to PNG and WebP without going through an SVG renderer.
+//!
+//! The drawing code for each icon family lives next to its SVG rendering.
+//! This module provides the pieces they share: mapping a viewBox onto a
+//! square bitmap, the drop shadow filter used by most of the templates, drawing
+//! emoji from their vector artwork and text with the bundled font, and
+//! encoding the result.
+
+use std::error::Error as StdError;
+use std::fmt;
+use tiny_skia::{
+    FillRule, Mask, Paint, Path, PathBuilder, Pixmap, PixmapPaint, Rect, Stroke, Transform,
+};
+
+use image_webp::{ColorType, WebPEncoder};
+use resvg::{self, usvg};
+
+use super::render::ViewBox;
+use super::{emoji, text, Color, Error};
+
+/// An error that occurred while rasterizing an icon.
+#[derive(Debug)]
+pub enum RasterError {
+    /// The requested image size can't be rendered.
+    InvalidSize(u32),
+    /// The rendered image couldn't be encoded as a PNG or WebP.
+    Encoding(String),
+}
+
+impl StdError for RasterError {
+    fn description(&self) -> &str {
+        match *self {
+            RasterError::InvalidSize(_) => "invalid image size",
+            RasterError::Encoding(_) => "could not encode image",
+        }
+    }
+}
+
+impl fmt::Display for RasterError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RasterError::InvalidSize(size) => write!(fmt, "can't render an image {}px wide", size),
+            RasterError::Encoding(ref msg) => write!(fmt, "could not encode image: {}", msg),
+        }
+    }
+}
+
+impl ViewBox {
+    /// The transform that maps this view box onto a `size`x`size` image,
+    /// matching SVG's default `preserveAspectRatio="xMidYMid meet"`.
+    fn transform(&self, size: u32) -> Transform {
+        let size = size as f32;
+        let scale = (size / self.width).min(size / self.height);
+        let tx = (size - self.width * scale) / 2.0 - self.x * scale;
+        let ty = (size - self.height * scale) / 2.0 - self.y * scale;
+        Transform::from_row(scale, 0.0, 0.0, scale, tx, ty)
+    }
+}
+
+/// The parameters of an `feDropShadow` filter, in user units.
+#[derive(Clone, Copy, Debug)]
+pub struct DropShadow {
+    pub dx: f32,
+    pub dy: f32,
+    pub std_deviation: f32,
+    pub opacity: f32,
+}
+
+/// Render an icon to a `size`x`size` bitmap, ready to be encoded with
+/// `encode_png` or `encode_webp`.
+///
+/// `draw` is called with a transparent layer and the transform from user
+/// space to pixels. Once it is done, `shadow` is applied to the layer, if
+/// there is one, the same way that the SVG templates apply a filter to their
+/// outermost group.
+pub fn render<F>(
+    size: u32,
+    view_box: ViewBox,
+    shadow: Option<DropShadow>,
+    draw: F,
+) -> Result<Pixmap, Error>
+where
+    F: FnOnce(&mut Pixmap, Transform) -> Result<(), Error>,
+{
+    let mut layer = Pixmap::new(size, size).ok_or(RasterError::InvalidSize(size))?;
+    let transform = view_box.transform(size);
+    draw(&mut layer, transform)?;
+
+    let mut image = Pixmap::new(size, size).ok_or(RasterError::InvalidSize(size))?;
+    if let Some(shadow) = shadow {
+        draw_shadow(&mut image, &layer, shadow, transform.sx);
+    }
+    image.draw_pixmap(
+        0,
+        0,
+        layer.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        None,
+    );
+    Ok(image)
+}
+
+/// Encode a rendered icon as a PNG.
+pub fn encode_png(image: &Pixmap) -> Result<Vec<u8>, Error> {
+    let png = image
+        .encode_png()
+        .map_err(|err| RasterError::Encoding(err.to_string()))?;
+    Ok(png)
+}
+
+/// Encode a rendered icon as a lossless WebP.
+pub fn encode_webp(image: &Pixmap) -> Result<Vec<u8>, Error> {
+    // Pixmaps are premultiplied, but WebP isn't.
+    let mut rgba = Vec::with_capacity(image.data().len());
+    for pixel in image.pixels() {
+        let pixel = pixel.demultiply();
+        rgba.extend_from_slice(&[pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]);
+    }
+    let mut webp = Vec::new();
+    WebPEncoder::new(&mut webp)
+        .encode(&rgba, image.width(), image.height(), ColorType::Rgba8)
+        .map_err(|err| RasterError::Encoding(err.to_string()))?;
+    Ok(webp)
+}
+
+/// A solid paint of the given color.
+pub fn paint(color: Color) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(color.r, color.g, color.b, 255);
+    paint.anti_alias = true;
+    paint
+}
+
+/// Fill `path` with `color`.
+pub fn fill_path(
+    pixmap: &mut Pixmap,
+    path: &Path,
+    color: Color,
+    transform: Transform,
+    clip: Option<&Mask>,
+) {
+    pixmap.fill_path(path, &paint(color), FillRule::Winding, transform, clip);
+}
+
+/// Stroke `path` with `color`, using a line `width` user units wide.
+pub fn stroke_path(
+    pixmap: &mut Pixmap,
+    path: &Path,
+    color: Color,
+    width: f32,
+    transform: Transform,
+    clip: Option<&Mask>,
+) {
+    let stroke = Stroke {
+        width,
+        ..Stroke::default()
+    };
+    pixmap.stroke_path(path, &paint(color), &stroke, transform, clip);
+}
+
+/// Fill a rectangle with `color`.
+///
+/// Like SVG, rectangles with a negative or zero size are not drawn.
+pub fn fill_rect(
+    pixmap: &mut Pixmap,
+    (x, y, width, height): (f32, f32, f32, f32),
+    color: Color,
+    transform: Transform,
+    clip: Option<&Mask>,
+) {
+    if width <= 0.0 || height <= 0.0 {
+        return;
+    }
+    if let Some(rect) = Rect::from_xywh(x, y, width, height) {
+        fill_path(
+            pixmap,
+            &PathBuilder::from_rect(rect),
+            color,
+            transform,
+            clip,
+        );
+    }
+}
+
+/// Fill the polygon with corners at `points` with `color`.
+pub fn fill_polygon(
+    pixmap: &mut Pixmap,
+    points: &[(f32, f32)],
+    color: Color,
+    transform: Transform,
+    clip: Option<&Mask>,
+) {
+    let mut builder = PathBuilder::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        if i == 0 {
+            builder.move_to(x, y);
+        } else {
+            builder.line_to(x, y);
+        }
+    }
+    builder.close();
+    if let Some(path) = builder.finish() {
+        fill_path(pixmap, &path, color, transform, clip);
+    }
+}
+
+/// Draw `emoji` from its vector artwork, into the square at `(x, y)` with
+/// sides `size` user units long.
+pub fn draw_emoji(
+    pixmap: &mut Pixmap,
+    emoji: char,
+    (x, y, size): (f32, f32, f32),
+    transform: Transform,
+    clip: Option<&Mask>,
+) -> Result<(), Error> {
+    let svg = emoji::svg(emoji)
+        .ok_or_else(|| Error::InvalidData(format!("there is no vector artwork for {:?}", emoji)))?;
+    let tree = usvg::Tree::from_str(svg, &usvg::Options::default())
+        .map_err(|err| Error::InvalidData(format!("invalid artwork for {:?}: {}", emoji, err)))?;
+
+    let scale = size / emoji::TWEMOJI_SIZE;
+    let transform = transform.pre_translate(x, y).pre_scale(scale, scale);
+    draw_tree(pixmap, &tree, transform, clip)
+}
+
+/// Draw a `<text>` element made by `text::svg`, with the bundled font.
+pub fn draw_text(
+    pixmap: &mut Pixmap,
+    element: &str,
+    transform: Transform,
+    clip: Option<&Mask>,
+) -> Result<(), Error> {
+    let tree = usvg::Tree::from_str(&text::wrap(element), &text::options())
+        .map_err(|err| Error::InvalidData(format!("invalid text: {}", err)))?;
+    draw_tree(pixmap, &tree, transform, clip)
+}
+
+/// Draw a parsed SVG onto `pixmap`, using `clip` if it is given.
+fn draw_tree(
+    pixmap: &mut Pixmap,
+    tree: &usvg::Tree,
+    transform: Transform,
+    clip: Option<&Mask>,
+) -> Result<(), Error> {
+    let mut layer = Pixmap::new(pixmap.width(), pixmap.height())
+        .ok_or_else(|| RasterError::InvalidSize(pixmap.width()))?;
+    resvg::render(tree, transform, &mut layer.as_mut());
+    pixmap.draw_pixmap(
+        0,
+        0,
+        layer.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        clip,
+    );
+    Ok(())
+}
+
+/// Build a clipping mask for `pixmap` out of `path`.
+pub fn clip_mask(pixmap: &Pixmap, path: &Path, transform: Transform) -> Option<Mask> {
+    let mut mask = Mask::new(pixmap.width(), pixmap.height())?;
+    mask.fill_path(path, FillRule::Winding, true, transform);
+    Some(mask)
+}
+
+/// Composite the drop shadow of `layer` onto `image`.
+///
+/// This follows `feDropShadow`: the alpha channel of the layer is blurred,
+/// offset, and flooded with black at the shadow's opacity.
+fn draw_shadow(image: &mut Pixmap, layer: &Pixmap, shadow: DropShadow, scale: f32) {
+    let width = layer.width() as usize;
+    let height = layer.height() as usize;
+    let mut alpha: Vec<f32> = layer
+        .pixels()
+        .iter()
+        .map(|p| p.alpha() as f32 / 255.0)
+        .collect();
+    gaussian_blur(&mut alpha, width, height, shadow.std_deviation * scale);
+
+    let dx = (shadow.dx * scale).round() as isize;
+    let dy = (shadow.dy * scale).round() as isize;
+    let pixels = image.data_mut();
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let (sx, sy) = (x - dx, y - dy);
+            if sx < 0 || sy < 0 || sx >= width as isize || sy >= height as isize {
+                continue;
+            }
+            let a = alpha[sy as usize * width + sx as usize] * shadow.opacity;
+            // Black is all zeros when premultiplied, so only alpha is set.
+            pixels[(y as usize * width + x as usize) * 4 + 3] = (a * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Approximate a gaussian blur with three box blurs, as described for
+/// `feGaussianBlur` in the SVG specification.
+fn gaussian_blur(values: &mut Vec<f32>, width: usize, height: usize, std_deviation: f32) {
+    let d =
+        (std_deviation * 3.0 * (2.0 * ::std::f32::consts::PI).sqrt() / 4.0 + 0.5).floor() as usize;
+    if d <= 1 {
+        return;
+    }
+    let passes = if d % 2 == 1 {
+        [(d, d / 2), (d, d / 2), (d, d / 2)]
+    } else {
+        [(d, d / 2), (d, d / 2 - 1), (d + 1, d / 2)]
+    };
+
+    let mut scratch = vec![0.0; values.len()];
+    for &(len, offset) in passes.iter() {
+        for y in 0..height {
+            box_blur(values, &mut scratch, y * width, 1, width, len, offset);
+        }
+        ::std::mem::swap(values, &mut scratch);
+    }
+    for &(len, offset) in passes.iter() {
+        for x in 0..width {
+            box_blur(values, &mut scratch, x, width, height, len, offset);
+        }
+        ::std::mem::swap(values, &mut scratch);
+    }
+}
+
+/// Average a window of `len` values around each value of one row or column,
+/// reading from `src` and writing into `dst`. The window for position `i`
+/// covers `i - offset .. i - offset + len`; values outside the image are 0.
+fn box_blur(
+    src: &[f32],
+    dst: &mut [f32],
+    start: usize,
+    step: usize,
+    count: usize,
+    len: usize,
+    offset: usize,
+) {
+    let at = |i: isize| -> f32 {
+        if i < 0 || i >= count as isize {
+            0.0
+        } else {
+            src[start + i as usize * step]
+        }
+    };
+    let mut sum: f32 = (0..len as isize).map(|i| at(i - offset as isize)).sum();
+    for i in 0..count as isize {
+        dst[start + i as usize * step] = (sum / len as f32).max(0.0);
+        sum += at(i - offset as isize + len as isize) - at(i - offset as isize);
+    }
+}
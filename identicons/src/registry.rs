@@ -0,0 +1,436 @@
+//! Looking up icon generators by their family and version.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use super::{
+    BlockiesIconData, Error, GenerationOptions, GridIconData, Identicon, ShapeIconData,
+    ShieldIconData,
+};
+
+/// A function that generates an icon of a version from the bytes of a seed.
+type BytesFn = fn(&[u8], u32, &GenerationOptions) -> Result<Box<dyn Identicon>, Error>;
+
+/// A function that generates an icon of a version from a precomputed digest.
+type DigestFn = fn(&[u8; 64], u32, &GenerationOptions) -> Result<Box<dyn Identicon>, Error>;
+
+/// The seed `Registry::register` checks new generators with.
+const PROBE_SEED: &str = "identicons";
+
+/// A function that reads an icon back from its JSON.
+type JsonFn = fn(&str) -> Result<Box<dyn Identicon>, Error>;
+
+/// Generates icons for one version of one family of icons.
+///
+/// Once a version is published, the icons it generates for each seed should
+/// never change. Changes to a family's algorithm ship as a new version, and
+/// the old one stays available alongside it.
+#[derive(Clone, Copy)]
+pub struct Generator {
+    family: &'static str,
+    version: u32,
+    from_bytes: BytesFn,
+    from_digest: DigestFn,
+    from_json: JsonFn,
+    accepts_key: bool,
+}
+
+impl Generator {
+    /// A generator for `version` of `T`, which uses
+    /// `T::from_bytes_with_version`, `T::from_digest_with_version` and
+    /// `T::from_json`.
+    ///
+    /// `family` and `version` must match the icons it generates, or
+    /// `Registry::register` will reject it.
+    pub fn of<T: Identicon + 'static>(family: &'static str, version: u32) -> Self {
+        Generator {
+            family,
+            version,
+            from_bytes: |bytes, version, options| {
+                Ok(Box::new(T::from_bytes_with_version(
+                    bytes, version, options,
+                )?))
+            },
+            from_digest: |digest, version, options| {
+                Ok(Box::new(T::from_digest_with_version(
+                    digest, version, options,
+                )?))
+            },
+            from_json: |json| Ok(Box::new(T::from_json(json)?)),
+            accepts_key: T::accepts_key(),
+        }
+    }
+
+    /// The name of the family of icons this generates, such as `"shield"`.
+    pub fn family(&self) -> &'static str {
+        self.family
+    }
+
+    /// The version of the family's algorithm this generates icons with.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// Whether this generator's icons can be generated with a seed key. See
+    /// `Identicon::accepts_key`.
+    pub fn accepts_key(&self) -> bool {
+        self.accepts_key
+    }
+
+    /// Generate an icon from a seed.
+    pub fn from_seed(&self, seed: &str) -> Result<Box<dyn Identicon>, Error> {
+        self.from_seed_with_options(seed, &GenerationOptions::default())
+    }
+
+    /// Generate an icon from a seed, customized by `options`.
+    pub fn from_seed_with_options(
+        &self,
+        seed: &str,
+        options: &GenerationOptions,
+    ) -> Result<Box<dyn Identicon>, Error> {
+        (self.from_bytes)(seed.as_bytes(), self.version, options)
+    }
+
+    /// Generate an icon from arbitrary bytes, customized by `options`.
+    pub fn from_bytes_with_options(
+        &self,
+        bytes: &[u8],
+        options: &GenerationOptions,
+    ) -> Result<Box<dyn Identicon>, Error> {
+        (self.from_bytes)(bytes, self.version, options)
+    }
+
+    /// Generate an icon from a precomputed SHA-512 digest, customized by
+    /// `options`.
+    pub fn from_digest_with_options(
+        &self,
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Box<dyn Identicon>, Error> {
+        (self.from_digest)(digest, self.version, options)
+    }
+
+    /// Read an icon back from its JSON.
+    pub fn from_json(&self, json: &str) -> Result<Box<dyn Identicon>, Error> {
+        (self.from_json)(json)
+    }
+}
+
+impl fmt::Debug for Generator {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "Generator({} v{})", self.family, self.version)
+    }
+}
+
+/// A collection of generators, keyed by family and version.
+///
+/// The default registry has every generator in this crate:
+///
+///     # use identicons::Registry;
+///     let registry = Registry::default();
+///     let generator = registry.get("shield", 1).unwrap();
+///     let icon = generator.from_seed("example").unwrap();
+///     assert_eq!((icon.family(), icon.version()), ("shield", 1));
+///
+#[derive(Debug, Clone)]
+pub struct Registry {
+    generators: BTreeMap<&'static str, BTreeMap<u32, Generator>>,
+}
+
+impl Registry {
+    /// Create a registry with no generators in it.
+    pub fn new() -> Self {
+        Registry {
+            generators: BTreeMap::new(),
+        }
+    }
+
+    /// Add a generator to this registry.
+    ///
+    /// Returns an error if there is already a generator for the same family
+    /// and version, since replacing it would change its icons, or if the
+    /// generator's icons are from a different family or version than it
+    /// claims.
+    pub fn register(&mut self, generator: Generator) -> Result<(), Error> {
+        let key = (generator.family, generator.version);
+        if self.get(key.0, key.1).is_some() {
+            return Err(Error::InvalidData(format!(
+                "{} v{} is already registered",
+                key.0, key.1
+            )));
+        }
+        let icon = generator.from_seed(PROBE_SEED)?;
+        if (icon.family(), icon.version()) != key {
+            return Err(Error::InvalidData(format!(
+                "the generator for {} v{} makes {} v{} icons",
+                key.0,
+                key.1,
+                icon.family(),
+                icon.version()
+            )));
+        }
+        self.generators
+            .entry(key.0)
+            .or_default()
+            .insert(key.1, generator);
+        Ok(())
+    }
+
+    /// Find the generator for a version of a family.
+    pub fn get(&self, family: &str, version: u32) -> Option<&Generator> {
+        self.generators.get(family)?.get(&version)
+    }
+
+    /// Find the newest version of a family.
+    pub fn latest(&self, family: &str) -> Option<&Generator> {
+        self.generators.get(family)?.values().next_back()
+    }
+
+    /// Every generator, ordered by family and then by version.
+    pub fn generators(&self) -> Vec<&Generator> {
+        self.generators.values().flat_map(|v| v.values()).collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        let mut registry = Registry::new();
+        let builtin = vec![
+            Generator::of::<ShieldIconData>("shield", 1),
+            Generator::of::<ShieldIconData>("shield", 2),
+            Generator::of::<ShieldIconData>("shield", 3),
+            Generator::of::<ShieldIconData>("shield", 4),
+            Generator::of::<ShapeIconData>("shape", 0),
+            Generator::of::<GridIconData>("grid", 1),
+            Generator::of::<BlockiesIconData>("blockies", 1),
+        ];
+        for generator in builtin {
+            registry
+                .register(generator)
+                .expect("built in generators are unique");
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use sha2::{Digest, Sha512};
+    use {Color, Palette, SeedKey};
+
+    #[test]
+    fn test_default_registry() {
+        let registry = Registry::default();
+        let keys: Vec<_> = registry
+            .generators()
+            .iter()
+            .map(|g| (g.family(), g.version()))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("blockies", 1),
+                ("grid", 1),
+                ("shape", 0),
+                ("shield", 1),
+                ("shield", 2),
+                ("shield", 3),
+                ("shield", 4)
+            ]
+        );
+
+        // Each generator makes icons that agree about where they came from.
+        for generator in registry.generators() {
+            let icon = generator.from_seed("one").unwrap();
+            assert_eq!(
+                (icon.family(), icon.version()),
+                (generator.family(), generator.version())
+            );
+            let json = icon.to_json().unwrap();
+            assert_eq!(generator.from_json(&json).unwrap().to_json().unwrap(), json);
+
+            let options = GenerationOptions::default();
+            let from_bytes = generator.from_bytes_with_options(b"one", &options).unwrap();
+            assert_eq!(from_bytes.to_json().unwrap(), json);
+            let mut digest = [0u8; 64];
+            digest.copy_from_slice(&Sha512::digest(b"one"));
+            match generator.from_digest_with_options(&digest, &options) {
+                Ok(from_digest) => assert_eq!(from_digest.to_json().unwrap(), json),
+                // Blockies are drawn from the seed itself, not its hash.
+                Err(_) => assert_eq!(generator.family(), "blockies"),
+            }
+        }
+
+        assert!(registry.get("shield", 0).is_none());
+        assert!(registry.get("hexagon", 1).is_none());
+        assert_eq!(registry.latest("shield").unwrap().version(), 4);
+    }
+
+    /// Every color in an icon's JSON.
+    fn json_colors(value: &serde_json::Value, colors: &mut Vec<Color>) {
+        match *value {
+            serde_json::Value::Object(ref map) => {
+                if let Ok(color) = serde_json::from_value::<Color>(value.clone()) {
+                    colors.push(color);
+                }
+                for child in map.values() {
+                    json_colors(child, colors);
+                }
+            }
+            serde_json::Value::Array(ref items) => {
+                for item in items {
+                    json_colors(item, colors);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Blockies follow their reference implementation instead of the
+    /// generation options, so they are tested on their own.
+    fn uses_options(generator: &Generator) -> bool {
+        generator.family() != "blockies"
+    }
+
+    #[test]
+    fn test_bytes_and_digest() {
+        let keyed = GenerationOptions {
+            key: Some(SeedKey::new("secret").unwrap()),
+            ..GenerationOptions::default()
+        };
+        let plain = GenerationOptions::default();
+        let mut digest = [0u8; 64];
+        digest.copy_from_slice(&Sha512::digest(b"one"));
+
+        for generator in Registry::default().generators() {
+            if !uses_options(generator) {
+                continue;
+            }
+            let json = generator.from_seed("one").unwrap().to_json().unwrap();
+            let from_digest = generator.from_digest_with_options(&digest, &plain).unwrap();
+            assert_eq!(from_digest.to_json().unwrap(), json, "{:?}", generator);
+
+            // Bytes don't have to be valid UTF-8.
+            generator
+                .from_bytes_with_options(&[0xff, 0x00, 0xc3], &plain)
+                .unwrap();
+
+            let from_keyed = generator.from_bytes_with_options(b"one", &keyed).unwrap();
+            assert_eq!(
+                from_keyed.to_json().unwrap(),
+                generator
+                    .from_seed_with_options("one", &keyed)
+                    .unwrap()
+                    .to_json()
+                    .unwrap(),
+                "{:?}",
+                generator
+            );
+            assert_ne!(from_keyed.to_json().unwrap(), json, "{:?}", generator);
+            assert!(generator.from_digest_with_options(&digest, &keyed).is_err());
+        }
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = Palette::from_json(r##"{"navy": "#001f3f", "gold": "#ffd700"}"##).unwrap();
+        let options = GenerationOptions {
+            palette: palette.clone(),
+            ..GenerationOptions::default()
+        };
+        let too_small = GenerationOptions {
+            palette: Palette::from_json(r##"{"white": "#ffffff"}"##).unwrap(),
+            ..GenerationOptions::default()
+        };
+
+        for generator in Registry::default().generators() {
+            if !uses_options(generator) {
+                continue;
+            }
+            for seed in &["one", "two", "three", "four"] {
+                let icon = generator.from_seed_with_options(seed, &options).unwrap();
+                let json = serde_json::from_str(&icon.to_json().unwrap()).unwrap();
+                let mut colors = vec![];
+                json_colors(&json, &mut colors);
+                assert!(!colors.is_empty(), "{:?}", generator);
+                for color in colors {
+                    assert!(
+                        palette.colors().contains(&color),
+                        "{:?} used {:?} for {:?}",
+                        generator,
+                        color,
+                        seed
+                    );
+                }
+            }
+
+            match generator.from_seed_with_options("one", &too_small) {
+                Err(Error::Palette(_)) => (),
+                other => panic!("expected a palette error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_frozen_versions() {
+        let mut registry = Registry::default();
+        assert!(registry
+            .register(Generator::of::<ShieldIconData>("shield", 1))
+            .is_err());
+        assert_eq!(
+            registry
+                .get("shield", 1)
+                .unwrap()
+                .from_seed("one")
+                .unwrap()
+                .family(),
+            "shield"
+        );
+
+        let mut registry = Registry::new();
+        registry
+            .register(Generator::of::<ShieldIconData>("shield", 1))
+            .unwrap();
+        registry
+            .register(Generator::of::<ShieldIconData>("shield", 2))
+            .unwrap();
+        assert_eq!(registry.latest("shield").unwrap().version(), 2);
+        let icon = registry.get("shield", 2).unwrap().from_seed("one").unwrap();
+        assert_eq!(icon.version(), 2);
+    }
+
+    #[test]
+    fn test_accepts_key() {
+        let keyed = GenerationOptions {
+            key: Some(SeedKey::new("secret").unwrap()),
+            ..GenerationOptions::default()
+        };
+        for generator in Registry::default().generators() {
+            let result = generator.from_seed_with_options("one", &keyed);
+            match (generator.accepts_key(), result) {
+                (true, Ok(_)) | (false, Err(Error::SeedKey(_))) => (),
+                (_, result) => panic!("{:?} gave {:?} for a key", generator, result),
+            }
+            assert_eq!(generator.accepts_key(), generator.family() != "blockies");
+        }
+    }
+
+    #[test]
+    fn test_mismatched_generators() {
+        let mut registry = Registry::new();
+        // Icons from another family.
+        assert!(registry
+            .register(Generator::of::<ShapeIconData>("shield", 5))
+            .is_err());
+        // Versions that don't exist.
+        assert!(registry
+            .register(Generator::of::<ShieldIconData>("shield", 5))
+            .is_err());
+        assert!(registry
+            .register(Generator::of::<GridIconData>("grid", 2))
+            .is_err());
+        assert!(registry.generators().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! Options that control how icons are rendered.
+
+use serde::Serialize;
+use serde_json;
+use sha2::{Digest, Sha512};
+use std::default::Default;
+
+use super::{emoji, Error};
+
+/// The region of user space that an icon is drawn in, like an SVG `viewBox`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ViewBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+impl ViewBox {
+    /// The smallest square view box that contains this one, centered on it.
+    fn square(&self) -> ViewBox {
+        let side = self.width.max(self.height);
+        ViewBox {
+            x: self.x - (side - self.width) / 2.0,
+            y: self.y - (side - self.height) / 2.0,
+            width: side,
+            height: side,
+        }
+    }
+}
+
+/// The shape of a rendered image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aspect {
+    /// The image is square, with the icon centered in it.
+    Square,
+    /// The image has the same aspect ratio as the icon itself.
+    Natural,
+}
+
+/// How emoji are drawn in SVGs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EmojiStyle {
+    /// As text, using whatever emoji font the viewer has.
+    Text,
+    /// As vector paths from the bundled Twemoji artwork, so that the icon
+    /// looks the same everywhere, even without an emoji font.
+    Vector,
+}
+
+/// Options for rendering an icon as an SVG.
+///
+///     # use identicons::{Aspect, RenderOptions};
+///     let options = RenderOptions {
+///         size: 64,
+///         aspect: Aspect::Natural,
+///         ..RenderOptions::default()
+///     };
+///
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderOptions {
+    /// The length of the longest side of the image, in pixels.
+    pub size: u32,
+    /// The shape of the image.
+    pub aspect: Aspect,
+    /// Whether to set `width` and `height` on the SVG. Without them, the
+    /// image scales to fit wherever it is embedded.
+    pub dimensions: bool,
+    /// A prefix for the `id` of every element in the SVG, so that several
+    /// icons can be inlined into one document without their filters and clip
+    /// paths interfering. It may only contain ASCII letters, digits, `-` and
+    /// `_`, and must start with a letter.
+    ///
+    /// If this isn't given, a prefix is derived from the icon itself, so that
+    /// different icons always use different IDs.
+    pub id_prefix: Option<String>,
+    /// How to draw the emoji.
+    pub emoji: EmojiStyle,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            size: 200,
+            aspect: Aspect::Square,
+            dimensions: true,
+            id_prefix: None,
+            emoji: EmojiStyle::Text,
+        }
+    }
+}
+
+/// The attributes of the root `<svg>` element, as used by the templates.
+#[derive(Debug, Serialize)]
+pub(crate) struct SvgAttributes {
+    view_box: String,
+    id_prefix: String,
+    dimensions: bool,
+    width: String,
+    height: String,
+}
+
+impl RenderOptions {
+    /// Size the root element of `icon`, which is drawn in `view_box`, and
+    /// choose the prefix for its element IDs.
+    pub(crate) fn svg_attributes<T: Serialize>(
+        &self,
+        icon: &T,
+        view_box: ViewBox,
+    ) -> Result<SvgAttributes, Error> {
+        let id_prefix = match self.id_prefix {
+            Some(ref prefix) => {
+                if !is_valid_id_prefix(prefix) {
+                    return Err(Error::InvalidData(format!(
+                        "{:?} is not a valid SVG id prefix",
+                        prefix
+                    )));
+                }
+                prefix.clone()
+            }
+            None => {
+                let hash = Sha512::digest(&serde_json::to_vec(icon)?);
+                let hex: Vec<String> = hash[..4].iter().map(|b| format!("{:02x}", b)).collect();
+                format!("identicon-{}-", hex.concat())
+            }
+        };
+
+        let view_box = match self.aspect {
+            Aspect::Square => view_box.square(),
+            Aspect::Natural => view_box,
+        };
+        let scale = self.size as f32 / view_box.width.max(view_box.height);
+        Ok(SvgAttributes {
+            view_box: format!(
+                "{} {} {} {}",
+                view_box.x, view_box.y, view_box.width, view_box.height
+            ),
+            dimensions: self.dimensions,
+            width: (view_box.width * scale).to_string(),
+            height: (view_box.height * scale).to_string(),
+            id_prefix,
+        })
+    }
+
+    /// The markup to draw `emoji` in the square at `(x, y)` with sides `size`
+    /// user units long, or an empty string if it should be drawn as text.
+    pub(crate) fn emoji_svg(
+        &self,
+        emoji: char,
+        (x, y, size): (f32, f32, f32),
+    ) -> Result<String, Error> {
+        match self.emoji {
+            EmojiStyle::Text => Ok(String::new()),
+            EmojiStyle::Vector => {
+                let contents = emoji::svg_contents(emoji).ok_or_else(|| {
+                    Error::InvalidData(format!("there is no vector artwork for {:?}", emoji))
+                })?;
+                Ok(format!(
+                    r#"<svg x="{x}" y="{y}" width="{size}" height="{size}" viewBox="0 0 {art} {art}">{contents}</svg>"#,
+                    x = x,
+                    y = y,
+                    size = size,
+                    art = emoji::TWEMOJI_SIZE,
+                    contents = contents
+                ))
+            }
+        }
+    }
+}
+
+/// Can `prefix` be safely used to start an element ID?
+fn is_valid_id_prefix(prefix: &str) -> bool {
+    match prefix.chars().next() {
+        Some(first) if first.is_ascii_alphabetic() => prefix
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIELD: ViewBox = ViewBox {
+        x: 0.0,
+        y: 0.0,
+        width: 100.0,
+        height: 105.0,
+    };
+
+    #[test]
+    fn test_square() {
+        let attrs = RenderOptions::default()
+            .svg_attributes(&(), SHIELD)
+            .unwrap();
+        assert_eq!(attrs.view_box, "-2.5 0 105 105");
+        assert_eq!((&attrs.width[..], &attrs.height[..]), ("200", "200"));
+    }
+
+    #[test]
+    fn test_natural() {
+        let options = RenderOptions {
+            size: 210,
+            aspect: Aspect::Natural,
+            ..RenderOptions::default()
+        };
+        let attrs = options.svg_attributes(&(), SHIELD).unwrap();
+        assert_eq!(attrs.view_box, "0 0 100 105");
+        assert_eq!((&attrs.width[..], &attrs.height[..]), ("200", "210"));
+    }
+
+    #[test]
+    fn test_no_dimensions() {
+        let options = RenderOptions {
+            dimensions: false,
+            ..RenderOptions::default()
+        };
+        let attrs = options.svg_attributes(&(), SHIELD).unwrap();
+        assert!(!attrs.dimensions);
+    }
+
+    #[test]
+    fn test_id_prefix() {
+        let options = RenderOptions::default();
+        let first = options.svg_attributes(&"first", SHIELD).unwrap();
+        let second = options.svg_attributes(&"second", SHIELD).unwrap();
+        assert!(first.id_prefix.starts_with("identicon-"));
+        assert_ne!(first.id_prefix, second.id_prefix);
+
+        let options = RenderOptions {
+            id_prefix: Some("avatar-7_".to_string()),
+            ..RenderOptions::default()
+        };
+        let attrs = options.svg_attributes(&"first", SHIELD).unwrap();
+        assert_eq!(attrs.id_prefix, "avatar-7_");
+
+        for bad in &["", "7up", "a\"b", "a b"] {
+            let options = RenderOptions {
+                id_prefix: Some(bad.to_string()),
+                ..RenderOptions::default()
+            };
+            assert!(options.svg_attributes(&"first", SHIELD).is_err());
+        }
+    }
+}
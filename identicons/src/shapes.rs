@@ -1,10 +1,15 @@
 extern crate rand;
 extern crate tera;
 
+use super::raster::{self, DropShadow};
+use super::render::{RenderOptions, ViewBox};
+use super::text::{self, TextArea};
 use super::{
-    data, genome::{Genome, GenomeGen, GenomeResult}, templ, Color,
+    genome::{Genome, GenomeGen, GenomeResult},
+    templ, Color, Error, GenerationOptions,
 };
 use std::default::Default;
+use tiny_skia::{PathBuilder, Pixmap};
 
 /// A shape.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -15,6 +20,18 @@ pub enum ShapeType {
     Circle,
 }
 
+/// The area that shapes are drawn in.
+const VIEW_BOX: ViewBox = ViewBox {
+    x: 0.0,
+    y: 0.0,
+    width: 1.0,
+    height: 1.0,
+};
+
+/// The square that the emoji is drawn in when it is drawn from its vector
+/// artwork, as `(x, y, size)`. This covers the emoji's glyph in the template.
+const EMOJI_BOX: (f32, f32, f32) = (0.26, 0.33, 0.48);
+
 /// A description of a shape icon.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ShapeIconData {
@@ -28,79 +45,222 @@ pub struct ShapeIconData {
     pub border_color: Color,
     /// The offset of the icon.
     pub offset: f32,
+    /// Text to draw instead of the emoji.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
 }
 
 impl ShapeIconData {
     /// Generate a ShapeIconData by hashing an input and choosing unpredictable
     /// values for all parameters.
-    pub fn from_input<'a, T: Into<String>>(input: T) -> GenomeResult<Self> {
-        let mut genome = Genome::via_sha512(input);
-        genome.gen()
+    pub fn from_input<'a, T: Into<String>>(input: T) -> Result<Self, Error> {
+        Self::from_input_with_options(input, &GenerationOptions::default())
+    }
+
+    /// Generate a ShapeIconData like `from_input`, customized by `options`.
+    pub fn from_input_with_options<T: Into<String>>(
+        input: T,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        Self::from_bytes_with_options(input.into().as_bytes(), options)
+    }
+
+    /// Generate a ShapeIconData by hashing arbitrary bytes, such as a UUID or a
+    /// public key. A string's UTF-8 bytes give the same icon as `from_input`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_options(bytes, &GenerationOptions::default())
+    }
+
+    /// Generate a ShapeIconData like `from_bytes`, customized by `options`.
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.genome(bytes);
+        genome.gen_with(options)
+    }
+
+    /// Generate a ShapeIconData from a SHA-512 digest that has already been
+    /// computed, such as a content hash. The digest of some bytes gives the
+    /// same icon as `from_bytes`.
+    pub fn from_digest(digest: &[u8; 64]) -> Result<Self, Error> {
+        Self::from_digest_with_options(digest, &GenerationOptions::default())
+    }
+
+    /// Generate a ShapeIconData like `from_digest`, customized by `options`.
+    ///
+    /// Returns an error if `options` has a key, since the digest is used as
+    /// is. Compute it with HMAC-SHA512 to key it.
+    pub fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.digest_genome(digest)?;
+        genome.gen_with(options)
     }
 
     /// Render as an SVG.
-    pub fn to_svg(&self) -> tera::Result<String> {
+    pub fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
         let mut context = tera::Context::new();
         context.add("icon", &self);
+        context.add("svg", &options.svg_attributes(self, VIEW_BOX)?);
+        if let Some(points) = self.polygon_points()? {
+            context.add("points", &points);
+        }
+        match self.text_svg()? {
+            Some(text_svg) => {
+                context.add("text_svg", &text_svg);
+                context.add("emoji_svg", &"");
+            }
+            None => {
+                context.add("text_svg", &"");
+                context.add("emoji_svg", &options.emoji_svg(self.emoji, EMOJI_BOX)?);
+            }
+        }
+        Ok(templ::render("shape.svg.tera", &context)?)
+    }
+
+    /// Render as a `size`x`size` PNG.
+    ///
+    /// The shape is drawn with the same geometry as the SVG, the emoji is
+    /// always drawn from its vector artwork, and text is always drawn with the
+    /// bundled font.
+    pub fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_png(&self.to_pixmap(size)?)
+    }
+
+    /// Render as a `size`x`size` lossless WebP, drawn the same way as
+    /// `to_png`.
+    pub fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_webp(&self.to_pixmap(size)?)
+    }
+
+    fn to_pixmap(&self, size: u32) -> Result<Pixmap, Error> {
+        let shadow = DropShadow {
+            dx: 0.01,
+            dy: 0.01,
+            std_deviation: 0.01,
+            opacity: 0.2,
+        };
+
+        let points = self.polygon_points()?;
+        let text_svg = self.text_svg()?;
+        raster::render(size, VIEW_BOX, Some(shadow), |pixmap, transform| {
+            let path = match points {
+                Some(points) => {
+                    let mut pb = PathBuilder::new();
+                    pb.move_to(points[0].0, points[0].1);
+                    for &(x, y) in &points[1..] {
+                        pb.line_to(x, y);
+                    }
+                    pb.close();
+                    pb.finish()
+                }
+                None => PathBuilder::from_circle(0.5, 0.5, 0.45),
+            };
+            if let Some(path) = path {
+                raster::fill_path(pixmap, &path, self.fill_color, transform, None);
+                raster::stroke_path(pixmap, &path, self.border_color, 0.02, transform, None);
+            }
+
+            match text_svg {
+                Some(text_svg) => raster::draw_text(pixmap, &text_svg, transform, None),
+                None => raster::draw_emoji(pixmap, self.emoji, EMOJI_BOX, transform, None),
+            }
+        })
+    }
+
+    /// The element that draws this icon's text, if it has any, sized to fit
+    /// inside the shape.
+    fn text_svg(&self) -> Result<Option<String>, Error> {
+        let t = match self.text {
+            Some(ref t) => t,
+            None => return Ok(None),
+        };
+        let radius = match self.shape {
+            ShapeType::Circle => 0.45,
+            ShapeType::Polygon(sides) => 0.45 * (::std::f32::consts::PI / sides as f32).cos(),
+        };
+        // Leave some space between the text and the border.
+        let area = TextArea::Circle(0.5, 0.5, radius * 0.8);
+        Ok(Some(text::svg(t, area, text::color_on(self.fill_color))?))
+    }
 
+    /// The corners of the shape, if it is a polygon, in a unit square.
+    ///
+    /// Returns an error if the shape is a polygon with too few sides to draw.
+    fn polygon_points(&self) -> Result<Option<Vec<(f32, f32)>>, Error> {
         if let ShapeType::Polygon(sides) = self.shape {
+            if sides < 3 {
+                return Err(Error::InvalidData(format!(
+                    "a polygon needs at least 3 sides, not {}",
+                    sides
+                )));
+            }
             let step = ::std::f32::consts::PI * 2.0 / (sides as f32);
             let offset = step * self.offset;
             let radius = 0.45;
-            let points: Vec<(f32, f32)> = (0..sides)
-                .map(|i| {
-                    let ang = step * i as f32 + offset;
-                    (ang.cos() * radius + 0.5, ang.sin() * radius + 0.5)
-                })
-                .collect();
-            context.add("points", &points);
+            Ok(Some(
+                (0..sides)
+                    .map(|i| {
+                        let ang = step * i as f32 + offset;
+                        (ang.cos() * radius + 0.5, ang.sin() * radius + 0.5)
+                    })
+                    .collect(),
+            ))
+        } else {
+            Ok(None)
         }
-
-        templ::render("shape.svg.tera", &context)
     }
 }
 
 impl GenomeGen for ShapeIconData {
-    fn gen(genome: &mut Genome) -> Result<Self, ()> {
-        let mut rv = ShapeIconData::default();
-
-        rv.emoji = genome.choose(&data::EMOJIS)?;
-
-        let white = Color::white();
-        let contrasts_with_white: Vec<Color> = data::COLORS
-            .iter()
-            .filter(|c| white.contrasts_well(c))
-            .map(|c| *c)
-            .collect();
+    fn gen_with(genome: &mut Genome, options: &GenerationOptions) -> GenomeResult<Self> {
+        let emoji = genome.choose(options.emoji.emoji())?;
+        let text = match options.text {
+            Some(ref t) => {
+                text::validate(t)?;
+                Some(t.clone())
+            }
+            None => None,
+        };
 
-        rv.border_color = genome.choose(&contrasts_with_white)?;
+        let contrasts_with_white = options
+            .palette
+            .pairable(Some(&Color::white()), options.contrast)?;
+        let border_color = genome.choose(&contrasts_with_white)?;
 
-        let contrasts_with_border: Vec<Color> = data::COLORS
-            .iter()
-            .filter(|c| rv.border_color.contrasts_well(c))
-            .map(|c| *c)
-            .collect();
-        rv.fill_color = genome.choose(&contrasts_with_border)?;
+        let contrasts_with_border = options
+            .palette
+            .contrasting_with(&border_color, options.contrast)?;
+        let fill_color = genome.choose(&contrasts_with_border)?;
 
         let num_sides: u8 = genome.gen_range(1u8, 10u8)?;
-        if num_sides <= 2 {
+        let shape = if num_sides <= 2 {
             // A polygon with 2 or fewer sides doesn't make sense, so make it a circle instead.
-            rv.shape = ShapeType::Circle;
+            ShapeType::Circle
         } else {
-            rv.shape = ShapeType::Polygon(num_sides);
-        }
+            ShapeType::Polygon(num_sides)
+        };
 
         // bias aligned and half aligned by giving then 1/4 of the space each
         let rotation: u8 = genome.gen_range(0, 100)?;
-        if rotation >= 75 {
-            rv.offset = 0.5;
+        let offset = if rotation >= 75 {
+            0.5
         } else if rotation >= 50 {
-            rv.offset = 0.0;
+            0.0
         } else {
-            rv.offset = rotation as f32 / 50.
-        }
+            rotation as f32 / 50.
+        };
 
-        Ok(rv)
+        Ok(ShapeIconData {
+            emoji,
+            shape,
+            fill_color,
+            border_color,
+            offset,
+            text,
+        })
     }
 }
 
@@ -112,6 +272,7 @@ impl Default for ShapeIconData {
             fill_color: Color::white(),
             border_color: Color::black(),
             offset: 0.0,
+            text: None,
         }
     }
 }
@@ -119,6 +280,7 @@ impl Default for ShapeIconData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use {ContrastMode, EmojiCategory, EmojiSet, Palette, SeedKey};
 
     /// Test that certain seeds always generate the same icon
     /// data. This is to make sure that icons don't change overtime,
@@ -140,6 +302,7 @@ mod tests {
                 b: 215,
             },
             offset: 0.0,
+            text: None,
         };
         let actual = ShapeIconData::from_input("one").unwrap();
         assert_eq!(expected, actual);
@@ -156,17 +319,142 @@ mod tests {
                 b: 11,
             },
             offset: 0.04,
+            text: None,
         };
         let actual = ShapeIconData::from_input("two").unwrap();
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_palette() {
+        let palette = Palette::from_json(r##"{"navy": "#001f3f", "gold": "#ffd700"}"##).unwrap();
+        let options = GenerationOptions {
+            palette: palette.clone(),
+            ..GenerationOptions::default()
+        };
+        let icon = ShapeIconData::from_input_with_options("one", &options).unwrap();
+        assert_eq!(icon.border_color, palette.get("navy").unwrap());
+        assert_eq!(icon.fill_color, palette.get("gold").unwrap());
+    }
+
+    #[test]
+    fn test_wcag_contrast() {
+        let options = GenerationOptions {
+            contrast: ContrastMode::WCAG_AA,
+            ..GenerationOptions::default()
+        };
+        for i in 0..50 {
+            let icon = ShapeIconData::from_input_with_options(i.to_string(), &options).unwrap();
+            assert!(icon.border_color.contrast_ratio(&Color::white()) >= 4.5);
+            assert!(icon.border_color.contrast_ratio(&icon.fill_color) >= 4.5);
+        }
+    }
+
+    #[test]
+    fn test_emoji_set() {
+        let options = GenerationOptions {
+            emoji: EmojiSet::from_categories(&[EmojiCategory::Animals]).unwrap(),
+            ..GenerationOptions::default()
+        };
+        for i in 0..20 {
+            let icon = ShapeIconData::from_input_with_options(i.to_string(), &options).unwrap();
+            assert_eq!(EmojiCategory::of(icon.emoji), Some(EmojiCategory::Animals));
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let options = GenerationOptions {
+            text: Some("MC".to_string()),
+            ..GenerationOptions::default()
+        };
+        let icon = ShapeIconData::from_input_with_options("one", &options).unwrap();
+        let plain = ShapeIconData::from_input("one").unwrap();
+        assert_eq!(icon.text, Some("MC".to_string()));
+        assert_eq!(
+            ShapeIconData {
+                text: None,
+                ..icon.clone()
+            },
+            plain
+        );
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(">MC</text>"));
+        assert!(!svg.contains(plain.emoji));
+        icon.to_png(100).unwrap();
+
+        let options = GenerationOptions {
+            text: Some("ABCD".to_string()),
+            ..GenerationOptions::default()
+        };
+        assert!(ShapeIconData::from_input_with_options("one", &options).is_err());
+    }
+
+    #[test]
+    fn test_seed_key() {
+        let keyed = |key: &str| GenerationOptions {
+            key: Some(SeedKey::new(key).unwrap()),
+            ..GenerationOptions::default()
+        };
+        let options = keyed("secret");
+        let plain = ShapeIconData::from_input("one").unwrap();
+        let icon = ShapeIconData::from_input_with_options("one", &options).unwrap();
+        assert_eq!(
+            icon,
+            ShapeIconData::from_input_with_options("one", &options).unwrap()
+        );
+        assert_ne!(icon, plain);
+        assert_ne!(
+            icon,
+            ShapeIconData::from_input_with_options("one", &keyed("other secret")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_png() {
+        let icon = ShapeIconData {
+            emoji: '🐶',
+            shape: ShapeType::Circle,
+            fill_color: Color { r: 0, g: 0, b: 255 },
+            border_color: Color { r: 0, g: 255, b: 0 },
+            offset: 0.0,
+            text: None,
+        };
+        let png = icon.to_png(100).unwrap();
+        let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (100, 100));
+
+        let rgba = |x, y| {
+            let p = pixmap.pixel(x, y).unwrap().demultiply();
+            (p.red(), p.green(), p.blue(), p.alpha())
+        };
+        assert_eq!(rgba(1, 1).3, 0);
+        assert_eq!(rgba(50, 5), (0, 255, 0, 255));
+        assert_eq!(rgba(50, 15), (0, 0, 255, 255));
+        // The emoji covers the middle of the shape.
+        assert_ne!(rgba(50, 60), (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn test_invalid_polygon() {
+        let icon = ShapeIconData {
+            shape: ShapeType::Polygon(2),
+            ..ShapeIconData::default()
+        };
+        match icon.to_svg(&RenderOptions::default()) {
+            Err(Error::InvalidData(_)) => (),
+            other => panic!("expected invalid data, got {:?}", other),
+        }
+        assert!(icon.to_png(100).is_err());
+    }
+
     proptest! {
         #[test]
         fn prop_repeatable_results(ref s in ".*") {
             let first = ShapeIconData::from_input(s.clone());
             let second = ShapeIconData::from_input(s.clone());
-            assert_eq!(first, second);
+            assert_eq!(first.ok(), second.ok());
         }
 
         #[test]
@@ -1,11 +1,17 @@
 extern crate rand;
 extern crate tera;
 
+use super::raster::{self, DropShadow};
+use super::render::{RenderOptions, ViewBox};
+use super::silhouettes::ShieldSilhouette;
 use super::templ;
+use super::text::{self, TextArea};
 use super::{
-    data, genome::{Genome, GenomeGen, GenomeResult}, Color,
+    genome::{Genome, GenomeGen, GenomeResult},
+    Color, Error, GenerationOptions,
 };
 use std::default::Default;
+use tiny_skia::{PathBuilder, Pixmap, Transform};
 
 /// A description of a treatment for a shield.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -34,6 +40,121 @@ pub enum ShieldIconTreatment {
         /// Angle of the stripes.
         angle: u16,
     },
+
+    /// Divided by a chevron, with the pattern color below it.
+    PerChevron {
+        /// The color below the chevron.
+        pattern_color: Color,
+        /// How far down the shield the point of the chevron is, from 0 to 1.
+        apex: f32,
+    },
+
+    /// A diagonal cross.
+    Saltire {
+        /// The color of the cross.
+        pattern_color: Color,
+        /// The width of each arm, as a fraction of the shield's width.
+        width: f32,
+    },
+
+    /// An upright cross.
+    Cross {
+        /// The color of the cross.
+        pattern_color: Color,
+        /// The width of each arm, as a fraction of the shield's width.
+        width: f32,
+    },
+
+    /// Divided into quarters, with the pattern color in the top left and
+    /// bottom right.
+    Quarterly {
+        /// The color of two of the quarters.
+        pattern_color: Color,
+    },
+
+    /// Divided diagonally from the top left, with the pattern color below.
+    PerBend {
+        /// The color below the division.
+        pattern_color: Color,
+    },
+
+    /// Divided diagonally from the top right, with the pattern color below.
+    PerBendSinister {
+        /// The color below the division.
+        pattern_color: Color,
+    },
+
+    /// Divided vertically, with the pattern color on the right.
+    PerPale {
+        /// The color of the right half.
+        pattern_color: Color,
+    },
+
+    /// A checkerboard.
+    Chequy {
+        /// The color of every other square.
+        pattern_color: Color,
+        /// How many squares there are across the shield, and down it.
+        squares: u8,
+    },
+}
+
+/// The area that shields are drawn in.
+const VIEW_BOX: ViewBox = ViewBox {
+    x: 0.0,
+    y: 0.0,
+    width: 100.0,
+    height: 105.0,
+};
+
+/// The square that the emoji is drawn in when it is drawn from its vector
+/// artwork, as `(x, y, size)`. This covers the emoji's glyph in the template.
+const EMOJI_BOX: (f32, f32, f32) = (26.0, 33.0, 48.0);
+
+/// Where text is drawn instead of the emoji, if there is any.
+const TEXT_AREA: TextArea = TextArea::Rect(20.0, 30.0, 60.0, 42.0);
+
+/// A small figure drawn on a shield, between its treatment and its emoji.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShieldCharge {
+    /// What the charge looks like.
+    pub shape: ShieldChargeShape,
+    /// The color of the charge.
+    pub color: Color,
+    /// The position of the center of the charge, from 0 to 100.
+    pub x: f32,
+    /// The position of the center of the charge, from 0 to 100.
+    pub y: f32,
+}
+
+/// The kinds of charges that can be drawn on a shield.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShieldChargeShape {
+    /// A five pointed star.
+    Mullet,
+    /// A disc.
+    Roundel,
+    /// A small upright cross.
+    Cross,
+}
+
+impl ShieldIconTreatment {
+    /// The color this treatment adds to the field, if it adds one.
+    pub fn pattern_color(&self) -> Option<Color> {
+        match *self {
+            ShieldIconTreatment::SingleColor => None,
+            ShieldIconTreatment::TwoColor { pattern_color, .. }
+            | ShieldIconTreatment::Stripes { pattern_color, .. }
+            | ShieldIconTreatment::PerChevron { pattern_color, .. }
+            | ShieldIconTreatment::Saltire { pattern_color, .. }
+            | ShieldIconTreatment::Cross { pattern_color, .. }
+            | ShieldIconTreatment::Quarterly { pattern_color }
+            | ShieldIconTreatment::PerBend { pattern_color }
+            | ShieldIconTreatment::PerBendSinister { pattern_color }
+            | ShieldIconTreatment::PerPale { pattern_color }
+            | ShieldIconTreatment::Chequy { pattern_color, .. } => Some(pattern_color),
+        }
+    }
 }
 
 /// A description of a shield icon.
@@ -42,47 +163,460 @@ pub struct ShieldIconData {
     treatment: ShieldIconTreatment,
     field_color: Color,
     emoji: char,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bordure: Option<Color>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    charges: Vec<ShieldCharge>,
+    #[serde(default, skip_serializing_if = "is_classic")]
+    silhouette: ShieldSilhouette,
+    /// The version of the algorithm that generated this icon. It is left out
+    /// of version 1's JSON, which predates it.
+    #[serde(default = "first_version", skip_serializing_if = "is_first_version")]
+    version: u32,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+fn is_first_version(version: &u32) -> bool {
+    *version == 1
+}
+
+fn is_classic(silhouette: &ShieldSilhouette) -> bool {
+    *silhouette == ShieldSilhouette::Classic
 }
 
 impl ShieldIconData {
     /// Generate a ShapeIconData by hashing an input and choosing unpredictable
     /// values for all parameters.
-    pub fn from_input<'a, T: Into<String>>(input: T) -> GenomeResult<Self> {
-        let mut genome = Genome::via_sha512(input);
-        genome.gen()
+    pub fn from_input<'a, T: Into<String>>(input: T) -> Result<Self, Error> {
+        Self::from_input_with_options(input, &GenerationOptions::default())
+    }
+
+    /// The newest version of the shield generation algorithm.
+    ///
+    /// `from_input`, `from_bytes` and `from_digest` always generate version 1
+    /// shields, so that they keep generating the same icons. Use
+    /// `from_bytes_with_version` or the `Registry` for newer versions.
+    pub const LATEST_VERSION: u32 = 4;
+
+    /// Generate a ShieldIconData like `from_input`, customized by `options`.
+    pub fn from_input_with_options<T: Into<String>>(
+        input: T,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        Self::from_bytes_with_options(input.into().as_bytes(), options)
+    }
+
+    /// Generate a ShieldIconData by hashing arbitrary bytes, such as a UUID or a
+    /// public key. A string's UTF-8 bytes give the same icon as `from_input`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Self::from_bytes_with_options(bytes, &GenerationOptions::default())
+    }
+
+    /// Generate a ShieldIconData like `from_bytes`, customized by `options`.
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.genome(bytes);
+        genome.gen_with(options)
+    }
+
+    /// Generate a ShieldIconData from a SHA-512 digest that has already been
+    /// computed, such as a content hash. The digest of some bytes gives the
+    /// same icon as `from_bytes`.
+    pub fn from_digest(digest: &[u8; 64]) -> Result<Self, Error> {
+        Self::from_digest_with_options(digest, &GenerationOptions::default())
+    }
+
+    /// Generate a ShieldIconData like `from_digest`, customized by `options`.
+    ///
+    /// Returns an error if `options` has a key, since the digest is used as
+    /// is. Compute it with HMAC-SHA512 to key it.
+    pub fn from_digest_with_options(
+        digest: &[u8; 64],
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        let mut genome = options.digest_genome(digest)?;
+        genome.gen_with(options)
+    }
+
+    /// Generate a ShieldIconData like `from_bytes_with_options`, with a
+    /// specific version of the algorithm.
+    ///
+    /// Returns an error if there is no such version.
+    pub fn from_bytes_with_version(
+        bytes: &[u8],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        check_version(version)?;
+        Self::generate(&mut options.genome(bytes), options, version)
+    }
+
+    /// Generate a ShieldIconData like `from_digest_with_options`, with a
+    /// specific version of the algorithm.
+    ///
+    /// Returns an error if there is no such version, or if `options` has a
+    /// key.
+    pub fn from_digest_with_version(
+        digest: &[u8; 64],
+        version: u32,
+        options: &GenerationOptions,
+    ) -> Result<Self, Error> {
+        check_version(version)?;
+        let mut genome = options.digest_genome(digest)?;
+        Self::generate(&mut genome, options, version)
+    }
+
+    /// The version of the algorithm that generated this icon.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// The color of the border around the edge of the shield, if it has one.
+    pub fn bordure(&self) -> Option<Color> {
+        self.bordure
+    }
+
+    /// The charges drawn on the shield.
+    pub fn charges(&self) -> &[ShieldCharge] {
+        &self.charges
+    }
+
+    /// The outline of the shield.
+    pub fn silhouette(&self) -> ShieldSilhouette {
+        self.silhouette
+    }
+
+    /// Is the point `(x, y)` on the shield? The point is in the same
+    /// coordinates as the SVG, where the shield is 100 units wide.
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        self.silhouette.contains(x / 100.0, y / 100.0)
     }
 
     /// Render as an SVG.
-    pub fn to_svg(&self) -> Result<String, tera::Error> {
+    pub fn to_svg(&self, options: &RenderOptions) -> Result<String, Error> {
         let mut context = tera::Context::new();
         context.add("icon", &self);
-        templ::render("shield.svg.tera", &context)
+        context.add("svg", &options.svg_attributes(self, VIEW_BOX)?);
+        // These are left out of the icon's JSON when they're empty.
+        match self.bordure {
+            Some(bordure) => context.add("bordure", &bordure),
+            None => context.add("bordure", &""),
+        }
+        context.add("charges", &self.charges);
+        context.add("silhouette", &self.silhouette.svg_commands());
+        match self.text_svg()? {
+            Some(text_svg) => {
+                context.add("text_svg", &text_svg);
+                context.add("emoji_svg", &"");
+            }
+            None => {
+                context.add("text_svg", &"");
+                context.add("emoji_svg", &options.emoji_svg(self.emoji, EMOJI_BOX)?);
+            }
+        }
+        Ok(templ::render("shield.svg.tera", &context)?)
     }
+
+    /// Render as a `size`x`size` PNG.
+    ///
+    /// The shield is drawn with the same geometry as the SVG, the emoji is
+    /// always drawn from its vector artwork, and text is always drawn with the
+    /// bundled font.
+    pub fn to_png(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_png(&self.to_pixmap(size)?)
+    }
+
+    /// Render as a `size`x`size` lossless WebP, drawn the same way as
+    /// `to_png`.
+    pub fn to_webp(&self, size: u32) -> Result<Vec<u8>, Error> {
+        raster::encode_webp(&self.to_pixmap(size)?)
+    }
+
+    fn to_pixmap(&self, size: u32) -> Result<Pixmap, Error> {
+        let shadow = DropShadow {
+            dx: 1.0,
+            dy: 1.0,
+            std_deviation: 1.0,
+            opacity: 0.2,
+        };
+
+        let text_svg = self.text_svg()?;
+        raster::render(size, VIEW_BOX, Some(shadow), |pixmap, transform| {
+            let unit = transform.pre_scale(100.0, 100.0);
+            let clip = raster::clip_mask(pixmap, &self.silhouette.path(), unit);
+            let clip = clip.as_ref();
+            raster::fill_rect(
+                pixmap,
+                (0.0, 0.0, 100.0, 100.0),
+                self.field_color,
+                transform,
+                clip,
+            );
+
+            match self.treatment {
+                ShieldIconTreatment::SingleColor => (),
+                ShieldIconTreatment::TwoColor {
+                    pattern_color,
+                    angle,
+                } => {
+                    let rotated =
+                        unit.pre_concat(Transform::from_rotate_at(angle as f32, 0.5, 0.5));
+                    raster::fill_rect(
+                        pixmap,
+                        (-0.25, -0.25, 1.5, 0.75),
+                        pattern_color,
+                        rotated,
+                        clip,
+                    );
+                }
+                ShieldIconTreatment::Stripes {
+                    pattern_color,
+                    stride,
+                    ref stripe_xs,
+                    angle,
+                } => {
+                    let rotated =
+                        unit.pre_concat(Transform::from_rotate_at(angle as f32, 0.5, 0.5));
+                    for &stripe_x in stripe_xs {
+                        raster::fill_rect(
+                            pixmap,
+                            (stripe_x, -0.25, stride, 1.5),
+                            pattern_color,
+                            rotated,
+                            clip,
+                        );
+                    }
+                }
+                ShieldIconTreatment::PerChevron {
+                    pattern_color,
+                    apex,
+                } => {
+                    raster::fill_polygon(
+                        pixmap,
+                        &CHEVRON,
+                        pattern_color,
+                        unit.pre_translate(0.0, apex),
+                        clip,
+                    );
+                }
+                ShieldIconTreatment::Saltire {
+                    pattern_color,
+                    width,
+                }
+                | ShieldIconTreatment::Cross {
+                    pattern_color,
+                    width,
+                } => {
+                    let angle = match self.treatment {
+                        ShieldIconTreatment::Saltire { .. } => 45.0,
+                        _ => 0.0,
+                    };
+                    let rotated = unit.pre_concat(Transform::from_rotate_at(angle, 0.5, 0.5));
+                    let edge = 0.5 - width / 2.0;
+                    for &rect in &[(-0.25, edge, 1.5, width), (edge, -0.25, width, 1.5)] {
+                        raster::fill_rect(pixmap, rect, pattern_color, rotated, clip);
+                    }
+                }
+                ShieldIconTreatment::Quarterly { pattern_color } => {
+                    for &rect in &[(0.0, 0.0, 0.5, 0.5), (0.5, 0.5, 0.5, 0.5)] {
+                        raster::fill_rect(pixmap, rect, pattern_color, unit, clip);
+                    }
+                }
+                ShieldIconTreatment::PerBend { pattern_color } => {
+                    raster::fill_polygon(pixmap, &BEND, pattern_color, unit, clip);
+                }
+                ShieldIconTreatment::PerBendSinister { pattern_color } => {
+                    raster::fill_polygon(pixmap, &BEND_SINISTER, pattern_color, unit, clip);
+                }
+                ShieldIconTreatment::PerPale { pattern_color } => {
+                    raster::fill_rect(pixmap, (0.5, 0.0, 0.5, 1.0), pattern_color, unit, clip);
+                }
+                ShieldIconTreatment::Chequy {
+                    pattern_color,
+                    squares,
+                } => {
+                    let square = unit.pre_scale(1.0 / squares as f32, 1.0 / squares as f32);
+                    for row in 0..squares {
+                        for column in 0..squares {
+                            if (row + column) % 2 == 1 {
+                                let rect = (column as f32, row as f32, 1.0, 1.0);
+                                raster::fill_rect(pixmap, rect, pattern_color, square, clip);
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(bordure) = self.bordure {
+                raster::stroke_path(
+                    pixmap,
+                    &self.silhouette.path(),
+                    bordure,
+                    BORDURE_WIDTH * 2.0,
+                    unit,
+                    clip,
+                );
+            }
+
+            for charge in &self.charges {
+                let placed = transform
+                    .pre_translate(charge.x, charge.y)
+                    .pre_scale(CHARGE_RADIUS, CHARGE_RADIUS);
+                match charge.shape {
+                    ShieldChargeShape::Mullet => {
+                        raster::fill_polygon(pixmap, &MULLET, charge.color, placed, clip);
+                    }
+                    ShieldChargeShape::Roundel => {
+                        if let Some(disc) = PathBuilder::from_circle(0.0, 0.0, 1.0) {
+                            raster::fill_path(pixmap, &disc, charge.color, placed, clip);
+                        }
+                    }
+                    ShieldChargeShape::Cross => {
+                        for &rect in &[(-1.0, -0.3, 2.0, 0.6), (-0.3, -1.0, 0.6, 2.0)] {
+                            raster::fill_rect(pixmap, rect, charge.color, placed, clip);
+                        }
+                    }
+                }
+            }
+
+            match text_svg {
+                Some(text_svg) => raster::draw_text(pixmap, &text_svg, transform, clip),
+                None => raster::draw_emoji(pixmap, self.emoji, EMOJI_BOX, transform, clip),
+            }
+        })
+    }
+
+    /// The element that draws this icon's text, if it has any.
+    fn text_svg(&self) -> Result<Option<String>, Error> {
+        match self.text {
+            Some(ref t) => Ok(Some(text::svg(
+                t,
+                TEXT_AREA,
+                text::color_on(self.field_color),
+            )?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// The corners of the part of a shield below a chevron, in a unit square,
+/// before it is moved down to its apex. These, and the other corners below,
+/// match the polygons in `shield.svg.tera`.
+const CHEVRON: [(f32, f32); 5] = [(0.0, 0.5), (0.5, 0.0), (1.0, 0.5), (1.0, 1.5), (0.0, 1.5)];
+
+/// The corners of the part of a shield below a bend.
+const BEND: [(f32, f32); 3] = [(0.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+/// The corners of the part of a shield below a bend sinister.
+const BEND_SINISTER: [(f32, f32); 3] = [(1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+
+/// How far the bordure reaches into the shield, in a unit square.
+const BORDURE_WIDTH: f32 = 0.07;
+
+/// The radius of each charge.
+const CHARGE_RADIUS: f32 = 5.0;
+
+/// The corners of a mullet with a radius of 1.
+const MULLET: [(f32, f32); 10] = [
+    (0.0, -1.0),
+    (0.225, -0.309),
+    (0.951, -0.309),
+    (0.363, 0.118),
+    (0.588, 0.809),
+    (0.0, 0.382),
+    (-0.588, 0.809),
+    (-0.363, 0.118),
+    (-0.951, -0.309),
+    (-0.225, -0.309),
+];
+
+/// The ways charges can be arranged, as the centers of each charge. They
+/// are all in the chief, above the emoji, except for the last of "two and
+/// one", which is in the base below it.
+const CHARGE_ARRANGEMENTS: [&[(f32, f32)]; 4] = [
+    &[(50.0, 22.0)],
+    &[(28.0, 25.0), (72.0, 25.0)],
+    &[(28.0, 25.0), (50.0, 22.0), (72.0, 25.0)],
+    &[(28.0, 25.0), (72.0, 25.0), (50.0, 89.0)],
+];
+
+/// Check that `version` is a version of the shield algorithm.
+fn check_version(version: u32) -> Result<(), Error> {
+    if !(1..=ShieldIconData::LATEST_VERSION).contains(&version) {
+        return Err(Error::InvalidData(format!(
+            "there is no version {} of shields",
+            version
+        )));
+    }
+    Ok(())
 }
 
+/// Generates the first version of shields, like `from_input`.
 impl GenomeGen for ShieldIconData {
-    fn gen(genome: &mut Genome) -> GenomeResult<Self> {
-        let mut rv = ShieldIconData::default();
+    fn gen_with(genome: &mut Genome, options: &GenerationOptions) -> GenomeResult<Self> {
+        Self::generate(genome, options, 1)
+    }
+}
+
+impl ShieldIconData {
+    /// Generate an icon by making each of its choices from `genome`, with
+    /// version `version` of the algorithm.
+    ///
+    /// Each version makes the same choices as the one before it, in the same
+    /// order, but may choose from more options.
+    fn generate(
+        genome: &mut Genome,
+        options: &GenerationOptions,
+        version: u32,
+    ) -> GenomeResult<Self> {
+        let mut rv = ShieldIconData {
+            version,
+            ..ShieldIconData::default()
+        };
 
         let angle_choices: Vec<u16> = (0..8).map(|a| a * 45).collect();
 
-        rv.field_color = genome.choose(&data::COLORS).unwrap();
-        let contrasting_colors: Vec<Color> = data::COLORS
-            .iter()
-            .filter(|c| rv.field_color.contrasts_well(c))
-            .map(|c| *c)
-            .collect();
-        rv.emoji = genome.choose(&data::EMOJIS).unwrap();
+        rv.field_color = genome.choose(&options.palette.pairable(None, options.contrast)?)?;
+        let contrasting_colors = options
+            .palette
+            .contrasting_with(&rv.field_color, options.contrast)?;
+        rv.emoji = genome.choose(options.emoji.emoji())?;
+        if let Some(ref t) = options.text {
+            text::validate(t)?;
+            rv.text = Some(t.clone());
+        }
 
-        let pattern_color = genome.choose(&contrasting_colors).unwrap();
+        let pattern_color = genome.choose(&contrasting_colors)?;
 
-        let treatment_name =
-            genome.choose_weighted(&vec![("SingleColor", 1), ("TwoColor", 4), ("Stripes", 6)])?;
+        let treatments = match version {
+            1 => vec![("SingleColor", 1), ("TwoColor", 4), ("Stripes", 6)],
+            _ => vec![
+                ("SingleColor", 1),
+                ("TwoColor", 3),
+                ("Stripes", 4),
+                ("PerChevron", 2),
+                ("Saltire", 2),
+                ("Cross", 2),
+                ("Quarterly", 2),
+                ("PerBend", 2),
+                ("PerBendSinister", 2),
+                ("PerPale", 2),
+                ("Chequy", 2),
+            ],
+        };
+        let treatment_name = genome.choose_weighted(&treatments)?;
 
         match treatment_name {
             "SingleColor" => (),
             "TwoColor" => {
-                let angle = genome.choose(&angle_choices).unwrap();
+                let angle = genome.choose(&angle_choices)?;
                 rv.treatment = ShieldIconTreatment::TwoColor {
                     angle,
                     pattern_color,
@@ -90,12 +624,18 @@ impl GenomeGen for ShieldIconData {
             }
             "Stripes" => {
                 let count: u8 = genome.gen_range(1, 4)?;
-                let padding = genome.gen_range(10u8, 40u8)? as f32 / 10.;
+                // Version 1 chose the padding in tenths instead of
+                // hundredths, so its stripes have a negative width and are
+                // never drawn.
+                let padding = match version {
+                    1 => genome.gen_range(10u8, 40u8)? as f32 / 10.,
+                    _ => genome.gen_range(10u8, 40u8)? as f32 / 100.,
+                };
                 let stride = (1.0 - 2.0 * padding) / (2.0 * count as f32 + 1.0);
                 let stripe_xs: Vec<f32> = (0..count)
                     .map(|i| padding + stride * (2 * i + 1) as f32)
                     .collect();
-                let angle = genome.choose(&angle_choices).unwrap();
+                let angle = genome.choose(&angle_choices)?;
                 rv.treatment = ShieldIconTreatment::Stripes {
                     stride,
                     stripe_xs,
@@ -103,9 +643,93 @@ impl GenomeGen for ShieldIconData {
                     angle,
                 };
             }
+            "PerChevron" => {
+                rv.treatment = ShieldIconTreatment::PerChevron {
+                    pattern_color,
+                    apex: genome.gen_range(20u8, 50u8)? as f32 / 100.,
+                };
+            }
+            "Saltire" => {
+                rv.treatment = ShieldIconTreatment::Saltire {
+                    pattern_color,
+                    width: genome.gen_range(10u8, 25u8)? as f32 / 100.,
+                };
+            }
+            "Cross" => {
+                rv.treatment = ShieldIconTreatment::Cross {
+                    pattern_color,
+                    width: genome.gen_range(10u8, 25u8)? as f32 / 100.,
+                };
+            }
+            "Quarterly" => rv.treatment = ShieldIconTreatment::Quarterly { pattern_color },
+            "PerBend" => rv.treatment = ShieldIconTreatment::PerBend { pattern_color },
+            "PerBendSinister" => {
+                rv.treatment = ShieldIconTreatment::PerBendSinister { pattern_color }
+            }
+            "PerPale" => rv.treatment = ShieldIconTreatment::PerPale { pattern_color },
+            "Chequy" => {
+                rv.treatment = ShieldIconTreatment::Chequy {
+                    pattern_color,
+                    squares: genome.gen_range(3u8, 7u8)?,
+                };
+            }
             _ => panic!("Unexpected treatment name"),
         }
 
+        if version >= 3 {
+            // The bordure and charges are drawn over the treatment, so they
+            // avoid its color, unless that is the only contrasting color.
+            let mut tinctures: Vec<Color> = contrasting_colors
+                .iter()
+                .cloned()
+                .filter(|&c| Some(c) != rv.treatment.pattern_color())
+                .collect();
+            if tinctures.is_empty() {
+                tinctures = contrasting_colors.clone();
+            }
+
+            if genome.choose_weighted(&vec![(false, 2), (true, 1)])? {
+                rv.bordure = Some(genome.choose(&tinctures)?);
+            }
+
+            let arrangement_weights = vec![
+                (None, 4),
+                (Some(0), 1),
+                (Some(1), 1),
+                (Some(2), 1),
+                (Some(3), 1),
+            ];
+            if let Some(arrangement) = genome.choose_weighted(&arrangement_weights)? {
+                let shape = genome.choose(&[
+                    ShieldChargeShape::Mullet,
+                    ShieldChargeShape::Roundel,
+                    ShieldChargeShape::Cross,
+                ])?;
+                let color = genome.choose(&tinctures)?;
+                rv.charges = CHARGE_ARRANGEMENTS[arrangement]
+                    .iter()
+                    .map(|&(x, y)| ShieldCharge { shape, color, x, y })
+                    .collect();
+            }
+        }
+
+        if version >= 4 {
+            rv.silhouette = genome.choose(&ShieldSilhouette::ALL)?;
+            if let Some(silhouette) = options.silhouette {
+                rv.silhouette = silhouette;
+            }
+            // Narrower silhouettes don't have room for every charge.
+            let silhouette = rv.silhouette;
+            rv.charges.retain(|charge| {
+                let r = CHARGE_RADIUS;
+                [(-r, -r), (r, -r), (-r, r), (r, r)]
+                    .iter()
+                    .all(|&(dx, dy)| {
+                        silhouette.contains((charge.x + dx) / 100.0, (charge.y + dy) / 100.0)
+                    })
+            });
+        }
+
         Ok(rv)
     }
 }
@@ -116,6 +740,11 @@ impl Default for ShieldIconData {
             treatment: ShieldIconTreatment::SingleColor,
             field_color: Color::black(),
             emoji: 'A',
+            text: None,
+            bordure: None,
+            charges: vec![],
+            silhouette: ShieldSilhouette::Classic,
+            version: 1,
         }
     }
 }
@@ -123,6 +752,12 @@ impl Default for ShieldIconData {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use image_webp::WebPDecoder;
+    use render::{Aspect, EmojiStyle};
+    use serde_json;
+    use std::collections::HashSet;
+    use std::io::Cursor;
+    use {ContrastMode, EmojiCategory, EmojiSet, Palette, SeedKey};
 
     /// Test that certain seeds always generate the same icon
     /// data. This is to make sure that icons don't change overtime,
@@ -145,6 +780,11 @@ mod tests {
                 },
                 angle: 0,
             },
+            text: None,
+            bordure: None,
+            charges: vec![],
+            silhouette: ShieldSilhouette::Classic,
+            version: 1,
         };
         let actual = ShieldIconData::from_input("one").unwrap();
         assert_eq!(expected, actual);
@@ -166,22 +806,464 @@ mod tests {
                 },
                 angle: 180,
             },
+            text: None,
+            bordure: None,
+            charges: vec![],
+            silhouette: ShieldSilhouette::Classic,
+            version: 1,
         };
         let actual = ShieldIconData::from_input("two").unwrap();
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_palette() {
+        let options = GenerationOptions {
+            palette: Palette::from_json(r##"{"navy": "#001f3f", "gold": "#ffd700"}"##).unwrap(),
+            ..GenerationOptions::default()
+        };
+        // Patterns always stand out from the field.
+        for seed in &["one", "two", "three", "four"] {
+            let icon = ShieldIconData::from_input_with_options(*seed, &options).unwrap();
+            if let Some(pattern_color) = icon.treatment.pattern_color() {
+                assert_ne!(pattern_color, icon.field_color);
+            }
+        }
+    }
+
+    #[test]
+    fn test_wcag_contrast() {
+        let options = GenerationOptions {
+            contrast: ContrastMode::WCAG_AAA,
+            ..GenerationOptions::default()
+        };
+        for i in 0..50 {
+            let icon = ShieldIconData::from_input_with_options(i.to_string(), &options).unwrap();
+            if let Some(pattern_color) = icon.treatment.pattern_color() {
+                assert!(icon.field_color.contrast_ratio(&pattern_color) >= 7.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_emoji_set() {
+        let options = GenerationOptions {
+            emoji: EmojiSet::from_categories(&[EmojiCategory::Animals]).unwrap(),
+            ..GenerationOptions::default()
+        };
+        for i in 0..20 {
+            let icon = ShieldIconData::from_input_with_options(i.to_string(), &options).unwrap();
+            assert_eq!(EmojiCategory::of(icon.emoji), Some(EmojiCategory::Animals));
+        }
+    }
+
+    #[test]
+    fn test_text() {
+        let options = GenerationOptions {
+            text: Some("MC".to_string()),
+            ..GenerationOptions::default()
+        };
+        let icon = ShieldIconData::from_input_with_options("one", &options).unwrap();
+        let plain = ShieldIconData::from_input("one").unwrap();
+        assert_eq!(icon.text, Some("MC".to_string()));
+        assert_eq!(
+            ShieldIconData {
+                text: None,
+                ..icon.clone()
+            },
+            plain
+        );
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(">MC</text>"));
+        assert!(!svg.contains(plain.emoji));
+        icon.to_png(100).unwrap();
+
+        let options = GenerationOptions {
+            text: Some("ABCD".to_string()),
+            ..GenerationOptions::default()
+        };
+        assert!(ShieldIconData::from_input_with_options("one", &options).is_err());
+    }
+
+    #[test]
+    fn test_seed_key() {
+        let keyed = |key: &str| GenerationOptions {
+            key: Some(SeedKey::new(key).unwrap()),
+            ..GenerationOptions::default()
+        };
+        let options = keyed("secret");
+        let plain = ShieldIconData::from_input("one").unwrap();
+        let icon = ShieldIconData::from_input_with_options("one", &options).unwrap();
+        assert_eq!(
+            icon,
+            ShieldIconData::from_input_with_options("one", &options).unwrap()
+        );
+        assert_ne!(icon, plain);
+        assert_ne!(
+            icon,
+            ShieldIconData::from_input_with_options("one", &keyed("other secret")).unwrap()
+        );
+        assert!(SeedKey::new("").is_err());
+        assert_eq!(format!("{:?}", options.key.unwrap()), "SeedKey(..)");
+    }
+
+    #[test]
+    fn test_svg_options() {
+        let icon = ShieldIconData::from_input("one").unwrap();
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(r#"viewBox="-2.5 0 105 105" width="200" height="200""#));
+
+        let options = RenderOptions {
+            size: 105,
+            aspect: Aspect::Natural,
+            ..RenderOptions::default()
+        };
+        let svg = icon.to_svg(&options).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 100 105" width="100" height="105""#));
+
+        let options = RenderOptions {
+            dimensions: false,
+            ..RenderOptions::default()
+        };
+        let svg = icon.to_svg(&options).unwrap();
+        assert!(svg.contains(r#"viewBox="-2.5 0 105 105" >"#));
+    }
+
+    #[test]
+    fn test_vector_emoji() {
+        let icon = ShieldIconData::from_input("one").unwrap();
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains("<text"));
+
+        let options = RenderOptions {
+            emoji: EmojiStyle::Vector,
+            ..RenderOptions::default()
+        };
+        let svg = icon.to_svg(&options).unwrap();
+        assert!(!svg.contains("<text"));
+        assert!(svg.contains(r#"<svg x="26" y="33" width="48" height="48" viewBox="0 0 36 36">"#));
+
+        let icon = ShieldIconData { emoji: 'A', ..icon };
+        assert!(icon.to_svg(&options).is_err());
+        assert!(icon.to_png(100).is_err());
+    }
+
+    #[test]
+    fn test_svg_ids() {
+        let one = ShieldIconData::from_input("one").unwrap();
+        let two = ShieldIconData::from_input("two").unwrap();
+        let options = RenderOptions::default();
+        let (one, two) = (one.to_svg(&options).unwrap(), two.to_svg(&options).unwrap());
+        let ids = |svg: &str| -> Vec<String> {
+            svg.split(r#"id=""#)
+                .skip(1)
+                .map(|s| s[..s.find('"').unwrap()].to_string())
+                .collect()
+        };
+        assert_eq!(ids(&one).len(), 2);
+        for id in ids(&one) {
+            assert!(one.contains(&format!("url(#{})", id)));
+            assert!(!ids(&two).contains(&id));
+        }
+    }
+
+    #[test]
+    fn test_png() {
+        let icon = ShieldIconData {
+            emoji: '🐶',
+            field_color: Color { r: 255, g: 0, b: 0 },
+            treatment: ShieldIconTreatment::SingleColor,
+            text: None,
+            bordure: None,
+            charges: vec![],
+            silhouette: ShieldSilhouette::Classic,
+            version: 1,
+        };
+        let png = icon.to_png(210).unwrap();
+        let pixmap = tiny_skia::Pixmap::decode_png(&png).unwrap();
+        assert_eq!((pixmap.width(), pixmap.height()), (210, 210));
+
+        // Outside the shield is transparent, inside is the field color.
+        assert_eq!(pixmap.pixel(2, 2).unwrap().alpha(), 0);
+        let inside = pixmap.pixel(105, 40).unwrap().demultiply();
+        assert_eq!(
+            (inside.red(), inside.green(), inside.blue(), inside.alpha()),
+            (255, 0, 0, 255)
+        );
+
+        assert!(icon.to_png(0).is_err());
+    }
+
+    #[test]
+    fn test_webp() {
+        let icon = ShieldIconData::from_input("one").unwrap();
+        let webp = icon.to_webp(64).unwrap();
+        let mut decoder = WebPDecoder::new(Cursor::new(&webp)).unwrap();
+        assert_eq!(decoder.dimensions(), (64, 64));
+        assert!(decoder.has_alpha());
+        let mut rgba = vec![0; decoder.output_buffer_size().unwrap()];
+        decoder.read_image(&mut rgba).unwrap();
+
+        // WebP is lossless, so it has exactly the same pixels as the PNG.
+        let png = tiny_skia::Pixmap::decode_png(&icon.to_png(64).unwrap()).unwrap();
+        for (i, pixel) in png.pixels().iter().enumerate() {
+            let pixel = pixel.demultiply();
+            assert_eq!(
+                &rgba[i * 4..i * 4 + 4],
+                &[pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+            );
+        }
+    }
+
+    #[test]
+    fn test_versions() {
+        let options = GenerationOptions::default();
+        assert_eq!(
+            ShieldIconData::from_bytes_with_version(b"one", 1, &options).unwrap(),
+            ShieldIconData::from_bytes(b"one").unwrap()
+        );
+        assert!(ShieldIconData::from_bytes_with_version(b"one", 0, &options).is_err());
+        let latest = ShieldIconData::LATEST_VERSION;
+        assert!(ShieldIconData::from_bytes_with_version(b"one", latest + 1, &options).is_err());
+
+        // Only later versions mention their version in their JSON.
+        let v1 = ShieldIconData::from_bytes(b"one").unwrap();
+        assert!(!serde_json::to_string(&v1).unwrap().contains("version"));
+        let v2 = ShieldIconData::from_bytes_with_version(b"one", 2, &options).unwrap();
+        assert_eq!(v2.version(), 2);
+        assert!(serde_json::to_string(&v2)
+            .unwrap()
+            .contains(r#""version":2"#));
+    }
+
+    #[test]
+    fn test_heraldic_treatments() {
+        let options = GenerationOptions::default();
+        let mut seen = HashSet::new();
+        for i in 0..200 {
+            let seed = i.to_string();
+            let icon =
+                ShieldIconData::from_bytes_with_version(seed.as_bytes(), 2, &options).unwrap();
+            let name = serde_json::to_value(&icon.treatment).unwrap()["type"].clone();
+            if seen.insert(name.as_str().unwrap().to_string()) {
+                assert!(icon.to_svg(&RenderOptions::default()).is_ok());
+                assert!(icon.to_png(32).is_ok());
+            }
+        }
+        for name in &[
+            "SingleColor",
+            "TwoColor",
+            "Stripes",
+            "PerChevron",
+            "Saltire",
+            "Cross",
+            "Quarterly",
+            "PerBend",
+            "PerBendSinister",
+            "PerPale",
+            "Chequy",
+        ] {
+            assert!(seen.contains(*name), "no shield had {}", name);
+        }
+    }
+
+    #[test]
+    fn test_per_pale_png() {
+        let icon = ShieldIconData {
+            emoji: '🐶',
+            field_color: Color { r: 255, g: 0, b: 0 },
+            treatment: ShieldIconTreatment::PerPale {
+                pattern_color: Color { r: 0, g: 0, b: 255 },
+            },
+            text: None,
+            bordure: None,
+            charges: vec![],
+            silhouette: ShieldSilhouette::Classic,
+            version: 2,
+        };
+        let pixmap = tiny_skia::Pixmap::decode_png(&icon.to_png(200).unwrap()).unwrap();
+        let left = pixmap.pixel(40, 60).unwrap().demultiply();
+        let right = pixmap.pixel(160, 60).unwrap().demultiply();
+        assert_eq!((left.red(), left.blue()), (255, 0));
+        assert_eq!((right.red(), right.blue()), (0, 255));
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(r#"x="0.5" y="0" width="0.5" height="1""#));
+    }
+
+    #[test]
+    fn test_bordure_and_charges() {
+        let options = GenerationOptions::default();
+        let mut bordures = 0;
+        let mut shapes = HashSet::new();
+        let mut arrangements = HashSet::new();
+        for i in 0..200 {
+            let seed = i.to_string();
+            let v2 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 2, &options).unwrap();
+            let v3 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 3, &options).unwrap();
+
+            // Version 3 only adds to version 2's choices.
+            assert_eq!(
+                ShieldIconData {
+                    bordure: None,
+                    charges: vec![],
+                    silhouette: ShieldSilhouette::Classic,
+                    version: 2,
+                    ..v3.clone()
+                },
+                v2
+            );
+
+            if let Some(bordure) = v3.bordure() {
+                assert_ne!(bordure, v3.field_color);
+                bordures += 1;
+            }
+            for charge in v3.charges() {
+                assert_ne!(charge.color, v3.field_color);
+                shapes.insert(format!("{:?}", charge.shape));
+            }
+            if !v3.charges().is_empty() {
+                let centers: Vec<_> = v3.charges().iter().map(|c| (c.x, c.y)).collect();
+                arrangements.insert(format!("{:?}", centers));
+            }
+        }
+        assert!(bordures > 0);
+        assert_eq!(shapes.len(), 3);
+        assert_eq!(arrangements.len(), CHARGE_ARRANGEMENTS.len());
+    }
+
+    #[test]
+    fn test_bordure_and_charges_png() {
+        let blue = Color { r: 0, g: 0, b: 255 };
+        let icon = ShieldIconData {
+            emoji: '🐶',
+            field_color: Color { r: 255, g: 0, b: 0 },
+            treatment: ShieldIconTreatment::SingleColor,
+            text: None,
+            bordure: Some(blue),
+            charges: vec![ShieldCharge {
+                shape: ShieldChargeShape::Roundel,
+                color: blue,
+                x: 50.0,
+                y: 22.0,
+            }],
+            silhouette: ShieldSilhouette::Classic,
+            version: 3,
+        };
+        // At this size, each unit is two pixels, and there are five pixels of
+        // space either side of the shield.
+        let pixmap = tiny_skia::Pixmap::decode_png(&icon.to_png(210).unwrap()).unwrap();
+        for &(x, y, expected) in &[(31, 100, blue), (105, 44, blue), (75, 50, icon.field_color)] {
+            let pixel = pixmap.pixel(x, y).unwrap().demultiply();
+            assert_eq!(
+                (pixel.red(), pixel.green(), pixel.blue()),
+                (expected.r, expected.g, expected.b),
+                "pixel at {}, {}",
+                x,
+                y
+            );
+        }
+
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains(r#"stroke="rgb(0,0,255)""#));
+        assert!(svg.contains(r#"<circle r="1" />"#));
+        let json = serde_json::to_string(&icon).unwrap();
+        assert!(json.contains(r#""bordure":{"r":0,"g":0,"b":255}"#));
+        assert!(json.contains(r#""charges":[{"shape":"Roundel""#));
+    }
+
+    #[test]
+    fn test_silhouettes() {
+        let options = GenerationOptions::default();
+        let mut silhouettes = HashSet::new();
+        for i in 0..200 {
+            let seed = i.to_string();
+            let v3 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 3, &options).unwrap();
+            let v4 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 4, &options).unwrap();
+            silhouettes.insert(v4.silhouette());
+
+            // Version 4 only adds a silhouette to version 3, and leaves off
+            // the charges that don't fit in it.
+            assert!(v4.charges().iter().all(|c| v3.charges().contains(c)));
+            assert_eq!(
+                ShieldIconData {
+                    charges: v3.charges.clone(),
+                    silhouette: ShieldSilhouette::Classic,
+                    version: 3,
+                    ..v4.clone()
+                },
+                v3
+            );
+            for charge in v4.charges() {
+                assert!(v4.contains(charge.x, charge.y));
+            }
+        }
+        assert_eq!(silhouettes.len(), ShieldSilhouette::ALL.len());
+
+        // Every charge fits in the classic silhouette.
+        let chosen = GenerationOptions {
+            silhouette: Some(ShieldSilhouette::Classic),
+            ..GenerationOptions::default()
+        };
+        for i in 0..50 {
+            let seed = i.to_string();
+            let v3 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 3, &options).unwrap();
+            let v4 = ShieldIconData::from_bytes_with_version(seed.as_bytes(), 4, &chosen).unwrap();
+            assert_eq!(ShieldIconData { version: 3, ..v4 }, v3);
+        }
+
+        // Older versions ignore the option.
+        let lozenge = GenerationOptions {
+            silhouette: Some(ShieldSilhouette::Lozenge),
+            ..GenerationOptions::default()
+        };
+        assert_eq!(
+            ShieldIconData::from_bytes_with_options(b"one", &lozenge).unwrap(),
+            ShieldIconData::from_bytes(b"one").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_silhouette_rendering() {
+        let options = GenerationOptions {
+            silhouette: Some(ShieldSilhouette::Lozenge),
+            ..GenerationOptions::default()
+        };
+        let icon = ShieldIconData::from_bytes_with_version(b"one", 4, &options).unwrap();
+        assert_eq!(icon.silhouette(), ShieldSilhouette::Lozenge);
+        assert!(serde_json::to_string(&icon)
+            .unwrap()
+            .contains(r#""silhouette":"Lozenge""#));
+        let svg = icon.to_svg(&RenderOptions::default()).unwrap();
+        assert!(svg.contains("L 0.9 0.5"));
+
+        // The corners of the square are outside a lozenge, and its middle is
+        // inside. At 210 pixels, each unit is two pixels, and there are five
+        // pixels of space either side of the shield.
+        let pixmap = tiny_skia::Pixmap::decode_png(&icon.to_png(210).unwrap()).unwrap();
+        assert!(!icon.contains(15.0, 15.0));
+        assert_eq!(pixmap.pixel(35, 30).unwrap().alpha(), 0);
+        assert!(icon.contains(20.0, 50.0));
+        assert_eq!(pixmap.pixel(45, 100).unwrap().alpha(), 255);
+    }
+
     proptest! {
         #[test]
         fn prop_repeatable_results(ref s in ".*") {
             let first = ShieldIconData::from_input(s.clone());
             let second = ShieldIconData::from_input(s.clone());
-            assert_eq!(first, second);
+            assert_eq!(first.ok(), second.ok());
         }
 
         #[test]
         fn prop_does_not_exhaust_genome(ref s in ".*") {
             assert!(ShieldIconData::from_input(s.clone()).is_ok());
+            let latest = ShieldIconData::LATEST_VERSION;
+            let options = GenerationOptions::default();
+            assert!(ShieldIconData::from_bytes_with_version(s.as_bytes(), latest, &options).is_ok());
         }
     }
 }
@@ -0,0 +1,259 @@
+//! The outlines that shields can have.
+//!
+//! Each outline is defined once, here, and used for the SVG clip path, for
+//! drawing PNGs, and for hit-testing.
+
+use tiny_skia::{Path, PathBuilder};
+
+/// The outline of a shield.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ShieldSilhouette {
+    /// A pointed top and a pointed base, the only outline before version 4.
+    #[default]
+    Classic,
+    /// A flat top, with sides that curve in to a point.
+    Heater,
+    /// A flat top and straight sides, with a small point in the middle of
+    /// the base.
+    French,
+    /// A top that rises to a point in the middle, and sides that curve in to
+    /// a point.
+    Swiss,
+    /// A flat top, and a base that is a half circle.
+    RoundBottomed,
+    /// A rounded top, narrowing to a point at the base.
+    Kite,
+    /// A diamond.
+    Lozenge,
+}
+
+/// One step of an outline, in a unit square.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Segment {
+    Move(f32, f32),
+    Line(f32, f32),
+    Cubic(f32, f32, f32, f32, f32, f32),
+}
+
+use self::Segment::{Cubic, Line, Move};
+
+// Each outline ends where it starts. Outlines with corners start in the
+// middle of a straight edge, so that the ends of a stroke around them meet
+// smoothly.
+
+const CLASSIC: &[Segment] = &[
+    Move(0.5, 0.0),
+    Cubic(0.6, 0.1, 0.7, 0.15, 0.9, 0.15),
+    Cubic(0.9, 0.5, 0.9, 0.8, 0.5, 1.0),
+    Cubic(0.1, 0.8, 0.1, 0.5, 0.1, 0.15),
+    Cubic(0.3, 0.15, 0.4, 0.1, 0.5, 0.0),
+];
+
+const HEATER: &[Segment] = &[
+    Move(0.5, 0.1),
+    Line(0.9, 0.1),
+    Line(0.9, 0.45),
+    Cubic(0.9, 0.75, 0.7, 0.9, 0.5, 1.0),
+    Cubic(0.3, 0.9, 0.1, 0.75, 0.1, 0.45),
+    Line(0.1, 0.1),
+    Line(0.5, 0.1),
+];
+
+const FRENCH: &[Segment] = &[
+    Move(0.5, 0.1),
+    Line(0.9, 0.1),
+    Line(0.9, 0.8),
+    Cubic(0.9, 0.88, 0.85, 0.9, 0.65, 0.9),
+    Cubic(0.55, 0.9, 0.5, 0.95, 0.5, 1.0),
+    Cubic(0.5, 0.95, 0.45, 0.9, 0.35, 0.9),
+    Cubic(0.15, 0.9, 0.1, 0.88, 0.1, 0.8),
+    Line(0.1, 0.1),
+    Line(0.5, 0.1),
+];
+
+const SWISS: &[Segment] = &[
+    Move(0.5, 0.05),
+    Cubic(0.55, 0.12, 0.7, 0.15, 0.9, 0.15),
+    Line(0.9, 0.55),
+    Cubic(0.9, 0.8, 0.7, 0.92, 0.5, 1.0),
+    Cubic(0.3, 0.92, 0.1, 0.8, 0.1, 0.55),
+    Line(0.1, 0.15),
+    Cubic(0.3, 0.15, 0.45, 0.12, 0.5, 0.05),
+];
+
+const ROUND_BOTTOMED: &[Segment] = &[
+    Move(0.5, 0.1),
+    Line(0.9, 0.1),
+    Line(0.9, 0.6),
+    Cubic(0.9, 0.82, 0.72, 1.0, 0.5, 1.0),
+    Cubic(0.28, 1.0, 0.1, 0.82, 0.1, 0.6),
+    Line(0.1, 0.1),
+    Line(0.5, 0.1),
+];
+
+const KITE: &[Segment] = &[
+    Move(0.5, 0.0),
+    Cubic(0.75, 0.0, 0.9, 0.12, 0.9, 0.3),
+    Cubic(0.9, 0.55, 0.65, 0.8, 0.5, 1.0),
+    Cubic(0.35, 0.8, 0.1, 0.55, 0.1, 0.3),
+    Cubic(0.1, 0.12, 0.25, 0.0, 0.5, 0.0),
+];
+
+const LOZENGE: &[Segment] = &[
+    Move(0.7, 0.25),
+    Line(0.9, 0.5),
+    Line(0.5, 1.0),
+    Line(0.1, 0.5),
+    Line(0.5, 0.0),
+    Line(0.7, 0.25),
+];
+
+/// How many straight lines each curve is split into for hit-testing.
+const CURVE_STEPS: usize = 16;
+
+impl ShieldSilhouette {
+    /// Every silhouette.
+    pub const ALL: [ShieldSilhouette; 7] = [
+        ShieldSilhouette::Classic,
+        ShieldSilhouette::Heater,
+        ShieldSilhouette::French,
+        ShieldSilhouette::Swiss,
+        ShieldSilhouette::RoundBottomed,
+        ShieldSilhouette::Kite,
+        ShieldSilhouette::Lozenge,
+    ];
+
+    fn segments(self) -> &'static [Segment] {
+        match self {
+            ShieldSilhouette::Classic => CLASSIC,
+            ShieldSilhouette::Heater => HEATER,
+            ShieldSilhouette::French => FRENCH,
+            ShieldSilhouette::Swiss => SWISS,
+            ShieldSilhouette::RoundBottomed => ROUND_BOTTOMED,
+            ShieldSilhouette::Kite => KITE,
+            ShieldSilhouette::Lozenge => LOZENGE,
+        }
+    }
+
+    /// The outline, in a unit square.
+    pub fn path(self) -> Path {
+        let mut pb = PathBuilder::new();
+        for &segment in self.segments() {
+            match segment {
+                Move(x, y) => pb.move_to(x, y),
+                Line(x, y) => pb.line_to(x, y),
+                Cubic(x1, y1, x2, y2, x, y) => pb.cubic_to(x1, y1, x2, y2, x, y),
+            }
+        }
+        pb.close();
+        pb.finish().expect("silhouettes are valid paths")
+    }
+
+    /// The commands of the outline's SVG path data, in a unit square. The
+    /// template joins them into a `d` attribute.
+    pub(crate) fn svg_commands(self) -> Vec<String> {
+        self.segments()
+            .iter()
+            .map(|segment| match *segment {
+                Move(x, y) => format!("M {:?} {:?}", x, y),
+                Line(x, y) => format!("L {:?} {:?}", x, y),
+                Cubic(x1, y1, x2, y2, x, y) => {
+                    format!("C {:?} {:?}, {:?} {:?}, {:?} {:?}", x1, y1, x2, y2, x, y)
+                }
+            })
+            .collect()
+    }
+
+    /// Is the point `(x, y)`, in a unit square, inside the outline?
+    pub fn contains(self, x: f32, y: f32) -> bool {
+        let points = self.polygon();
+        let mut inside = false;
+        for (i, &(x1, y1)) in points.iter().enumerate() {
+            let (x2, y2) = points[(i + 1) % points.len()];
+            if (y1 > y) != (y2 > y) && x < x1 + (y - y1) / (y2 - y1) * (x2 - x1) {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    /// The outline with its curves approximated by straight lines.
+    fn polygon(self) -> Vec<(f32, f32)> {
+        let mut points = vec![];
+        let mut current = (0.0, 0.0);
+        for &segment in self.segments() {
+            match segment {
+                Move(x, y) | Line(x, y) => points.push((x, y)),
+                Cubic(x1, y1, x2, y2, x, y) => {
+                    let (x0, y0) = current;
+                    for step in 1..=CURVE_STEPS {
+                        let t = step as f32 / CURVE_STEPS as f32;
+                        let u = 1.0 - t;
+                        let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+                        points.push((
+                            a * x0 + b * x1 + c * x2 + d * x,
+                            a * y0 + b * y1 + c * y2 + d * y,
+                        ));
+                    }
+                }
+            }
+            current = *points.last().expect("outlines start with a move");
+        }
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_commands() {
+        assert_eq!(
+            ShieldSilhouette::Classic.svg_commands(),
+            vec![
+                "M 0.5 0.0",
+                "C 0.6 0.1, 0.7 0.15, 0.9 0.15",
+                "C 0.9 0.5, 0.9 0.8, 0.5 1.0",
+                "C 0.1 0.8, 0.1 0.5, 0.1 0.15",
+                "C 0.3 0.15, 0.4 0.1, 0.5 0.0",
+            ]
+        );
+        assert_eq!(ShieldSilhouette::Lozenge.svg_commands()[1], "L 0.9 0.5");
+    }
+
+    #[test]
+    fn test_outlines_are_closed() {
+        for &silhouette in &ShieldSilhouette::ALL {
+            let polygon = silhouette.polygon();
+            let first = polygon[0];
+            let last = polygon[polygon.len() - 1];
+            assert!(
+                (first.0 - last.0).abs() < 1e-6 && (first.1 - last.1).abs() < 1e-6,
+                "{:?} doesn't end where it starts",
+                silhouette
+            );
+        }
+    }
+
+    #[test]
+    fn test_contains() {
+        for &silhouette in &ShieldSilhouette::ALL {
+            // Every shield covers its middle, and none reach the corners.
+            assert!(silhouette.contains(0.5, 0.5), "{:?}", silhouette);
+            for &(x, y) in &[(0.02, 0.02), (0.98, 0.02), (0.02, 0.98), (0.98, 0.98)] {
+                assert!(
+                    !silhouette.contains(x, y),
+                    "{:?} at {}, {}",
+                    silhouette,
+                    x,
+                    y
+                );
+            }
+        }
+        assert!(ShieldSilhouette::Heater.contains(0.15, 0.15));
+        assert!(!ShieldSilhouette::Lozenge.contains(0.15, 0.15));
+        assert!(ShieldSilhouette::RoundBottomed.contains(0.5, 0.95));
+        assert!(!ShieldSilhouette::Classic.contains(0.15, 0.95));
+    }
+}
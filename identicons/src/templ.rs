@@ -12,6 +12,8 @@ lazy_static! {
         engine.add_raw_templates(vec![
             ("shield.svg.tera", include_str!("templates/shield.svg.tera")),
             ("shape.svg.tera", include_str!("templates/shape.svg.tera")),
+            ("grid.svg.tera", include_str!("templates/grid.svg.tera")),
+            ("blockies.svg.tera", include_str!("templates/blockies.svg.tera")),
         ]).unwrap();
         engine.register_filter("css", tera_to_css);
         engine
@@ -0,0 +1,231 @@
+//! Drawing short text, such as initials, in place of an icon's emoji.
+
+use resvg::usvg::{self, fontdb};
+use std::sync::{Arc, OnceLock};
+use ttf_parser::Face;
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::{Color, Error};
+
+/// The most grapheme clusters that can be drawn on an icon.
+pub const MAX_GRAPHEMES: usize = 3;
+
+/// The fonts to use in SVGs, in order of preference. The first is bundled,
+/// and is the only one used to measure and rasterize text.
+const FONT_FAMILY: &str = "DejaVu Sans, Verdana, sans-serif";
+
+static FONTS: OnceLock<Arc<fontdb::Database>> = OnceLock::new();
+
+/// The fonts text is drawn with. Only the bundled font is loaded, so that
+/// text is measured and drawn the same way on every machine.
+pub fn fonts() -> Arc<fontdb::Database> {
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = fontdb::Database::new();
+            fonts.load_font_data(include_bytes!("fonts/DejaVuSans-Bold.ttf").to_vec());
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+/// The region of an icon that text should fit in.
+#[derive(Clone, Copy, Debug)]
+pub enum TextArea {
+    /// A rectangle, as `(x, y, width, height)`.
+    Rect(f32, f32, f32, f32),
+    /// A circle, as `(cx, cy, radius)`.
+    Circle(f32, f32, f32),
+}
+
+/// Check that `text` is short enough, and printable enough, to be drawn.
+///
+/// Every character must be in the bundled font, which has no emoji, so text
+/// such as `"👩‍👩‍👧"` is rejected rather than drawn as missing glyphs.
+pub fn validate(text: &str) -> Result<(), Error> {
+    let length = text.graphemes(true).count();
+    if length == 0 || length > MAX_GRAPHEMES {
+        return Err(Error::InvalidData(format!(
+            "text must be 1 to {} characters long, but {:?} is {}",
+            MAX_GRAPHEMES, text, length
+        )));
+    }
+    if text.trim().is_empty() || text.chars().any(char::is_control) {
+        return Err(Error::InvalidData(format!("{:?} can't be drawn", text)));
+    }
+    if let Some(c) = text.chars().find(|&c| !has_glyph(c)) {
+        return Err(Error::InvalidData(format!(
+            "the font has no glyph for {:?} in {:?}",
+            c, text
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the bundled font can draw `c`. Joiners and variation selectors
+/// only change how their neighbours are drawn, so they don't need glyphs.
+fn has_glyph(c: char) -> bool {
+    if c == '\u{200C}' || c == '\u{200D}' || ('\u{FE00}'..='\u{FE0F}').contains(&c) {
+        return true;
+    }
+    let fonts = fonts();
+    let found = fonts.faces().any(|face| {
+        fonts
+            .with_face_data(face.id, |data, index| {
+                Face::parse(data, index)
+                    .map(|face| face.glyph_index(c).is_some())
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+    });
+    found
+}
+
+/// Black or white, whichever is easier to read on `background`.
+pub fn color_on(background: Color) -> Color {
+    let (black, white) = (Color::black(), Color::white());
+    if background.contrast_ratio(&black) > background.contrast_ratio(&white) {
+        black
+    } else {
+        white
+    }
+}
+
+/// A `<text>` element that draws `text` in `color`, as large as it can be
+/// while still fitting in `area`.
+///
+/// The size and position are worked out from the bundled font, so the text
+/// only fits exactly when the SVG is viewed with that font installed, or
+/// when it is rasterized by this crate.
+pub fn svg(text: &str, area: TextArea, color: Color) -> Result<String, Error> {
+    validate(text)?;
+
+    // Lay the text out at a known size, to find how big its glyphs are.
+    const PROBE_SIZE: f32 = 100.0;
+    let probe = element(text, PROBE_SIZE, (0.0, 0.0), color);
+    let tree = usvg::Tree::from_str(&wrap(&probe), &options())
+        .map_err(|err| Error::InvalidData(format!("can't lay out {:?}: {}", text, err)))?;
+    let ink = tree.root().bounding_box();
+    if ink.width() <= 0.0 || ink.height() <= 0.0 {
+        return Err(Error::InvalidData(format!(
+            "the font has nothing to draw for {:?}",
+            text
+        )));
+    }
+    let (width, height) = (ink.width() / PROBE_SIZE, ink.height() / PROBE_SIZE);
+    let (mid_x, mid_y) = (
+        (ink.left() + ink.right()) / 2.0 / PROBE_SIZE,
+        (ink.top() + ink.bottom()) / 2.0 / PROBE_SIZE,
+    );
+
+    let (cx, cy, size) = match area {
+        TextArea::Rect(x, y, w, h) => (x + w / 2.0, y + h / 2.0, (w / width).min(h / height)),
+        TextArea::Circle(cx, cy, r) => (cx, cy, 2.0 * r / (width * width + height * height).sqrt()),
+    };
+    Ok(element(
+        text,
+        size,
+        (cx - mid_x * size, cy - mid_y * size),
+        color,
+    ))
+}
+
+/// Options for parsing SVGs that contain text.
+pub fn options<'a>() -> usvg::Options<'a> {
+    usvg::Options {
+        font_family: "DejaVu Sans".to_string(),
+        fontdb: fonts(),
+        ..usvg::Options::default()
+    }
+}
+
+/// Wrap an element from an icon in an SVG document, so it can be parsed on
+/// its own. The document has the same user space as the icon.
+pub fn wrap(element: &str) -> String {
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1">{}</svg>"#,
+        element
+    )
+}
+
+fn element(text: &str, size: f32, (x, y): (f32, f32), color: Color) -> String {
+    format!(
+        r#"<text fill="{}" font-family="{}" font-size="{}" font-weight="bold" text-anchor="middle" x="{}" y="{}">{}</text>"#,
+        color.css_color(),
+        FONT_FAMILY,
+        size,
+        x,
+        y,
+        escape(text)
+    )
+}
+
+/// Escape `text` for use as the contents of an XML element.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        for good in &["M", "MC", "abc", "é", "e\u{301}ab", "Ж", "&"] {
+            assert!(validate(good).is_ok(), "{:?} should be valid", good);
+        }
+        for bad in &["", "abcd", " ", "a\nb", "👩‍👩‍👧", "M🙂", "漢"] {
+            assert!(validate(bad).is_err(), "{:?} should be invalid", bad);
+        }
+    }
+
+    #[test]
+    fn test_fits_area() {
+        let options = options();
+        for text in &["I", "MW", "W&W"] {
+            for &area in &[
+                TextArea::Rect(20.0, 30.0, 60.0, 40.0),
+                TextArea::Circle(50.0, 50.0, 30.0),
+            ] {
+                let markup = svg(text, area, Color::white()).unwrap();
+                let tree = usvg::Tree::from_str(&wrap(&markup), &options).unwrap();
+                let ink = tree.root().bounding_box();
+                let close = |a: f32, b: f32| (a - b).abs() < 0.5;
+                match area {
+                    TextArea::Rect(x, y, w, h) => {
+                        assert!(ink.left() >= x - 0.5 && ink.right() <= x + w + 0.5);
+                        assert!(ink.top() >= y - 0.5 && ink.bottom() <= y + h + 0.5);
+                        // One of the dimensions should be filled.
+                        assert!(close(ink.width(), w) || close(ink.height(), h));
+                    }
+                    TextArea::Circle(cx, cy, r) => {
+                        let corner = (ink.width().powi(2) + ink.height().powi(2)).sqrt() / 2.0;
+                        assert!(close(corner, r));
+                        assert!(close((ink.left() + ink.right()) / 2.0, cx));
+                        assert!(close((ink.top() + ink.bottom()) / 2.0, cy));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_escape() {
+        let markup = svg("<&>", TextArea::Circle(0.5, 0.5, 0.4), Color::black()).unwrap();
+        assert!(markup.ends_with(">&lt;&amp;&gt;</text>"));
+    }
+
+    #[test]
+    fn test_color_on() {
+        assert_eq!(color_on(Color::white()), Color::black());
+        assert_eq!(color_on(Color::black()), Color::white());
+    }
+}
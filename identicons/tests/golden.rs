@@ -0,0 +1,105 @@
+//! Check that every generator still renders the same SVG and JSON for a
+//! corpus of seeds as it did when the expected output was checked in.
+//!
+//! See `tests/golden/README.md` for how to update the expected output.
+
+extern crate identicons;
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use identicons::{Registry, RenderOptions};
+
+/// Set this environment variable to write the current output as the new
+/// expected output, instead of comparing against it.
+const BLESS_VAR: &str = "IDENTICONS_BLESS";
+
+fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+fn seeds() -> Vec<String> {
+    let seeds = fs::read_to_string(golden_dir().join("seeds.txt")).expect("reading seeds.txt");
+    seeds.lines().map(|s| s.to_string()).collect()
+}
+
+/// A description of the first difference between two files.
+fn first_difference(expected: &str, actual: &str) -> String {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (Some(e), Some(a)) if e == a => line += 1,
+            (e, a) => {
+                return format!(
+                    "line {}:\n    expected: {}\n    actual:   {}",
+                    line,
+                    e.unwrap_or("<end of file>"),
+                    a.unwrap_or("<end of file>")
+                )
+            }
+        }
+    }
+}
+
+#[test]
+fn test_golden_files() {
+    let bless = env::var_os(BLESS_VAR).is_some();
+    let seeds = seeds();
+    let options = RenderOptions::default();
+    let mut failures = Vec::new();
+
+    for generator in Registry::default().generators() {
+        let dir = golden_dir()
+            .join(generator.family())
+            .join(format!("v{}", generator.version()));
+        if bless {
+            fs::create_dir_all(&dir).unwrap();
+        }
+
+        for (i, seed) in seeds.iter().enumerate() {
+            let icon = generator.from_seed(seed).unwrap();
+            let outputs = vec![
+                ("svg", icon.to_svg(&options).unwrap()),
+                ("json", icon.to_json().unwrap() + "\n"),
+            ];
+
+            for (extension, actual) in outputs {
+                let path = dir.join(format!("{:03}.{}", i, extension));
+                if bless {
+                    fs::write(&path, &actual).unwrap();
+                    continue;
+                }
+                match fs::read_to_string(&path) {
+                    Ok(ref expected) if *expected == actual => (),
+                    Ok(expected) => failures.push(format!(
+                        "{} (seed {:?}) changed at {}",
+                        path.display(),
+                        seed,
+                        first_difference(&expected, &actual)
+                    )),
+                    Err(err) => failures.push(format!(
+                        "{} (seed {:?}) couldn't be read: {}",
+                        path.display(),
+                        seed,
+                        err
+                    )),
+                }
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        panic!(
+            "{} rendered files don't match the golden files:\n\n{}\n\n\
+             If these changes are intended, rerun this test with {}=1 set, and \
+             check in the changes. Published versions should never change, so \
+             this is usually only right for a new version.",
+            failures.len(),
+            failures.join("\n\n"),
+            BLESS_VAR
+        );
+    }
+}